@@ -0,0 +1,338 @@
+//! Local test server
+//!
+//! A minimal in-memory stand-in for the GCopy server, enough to exercise the
+//! desktop client's sync paths without an account or SMTP setup. Every
+//! request is treated as the same logged-in user.
+//!
+//! ```bash
+//! # Push channel and polling
+//! cargo run --example test_server
+//! # Polling only, as with servers that predate the push channel
+//! cargo run --example test_server -- --polling-only
+//! ```
+//!
+//! Then set the server URL in the app to `http://127.0.0.1:3376`, or pass
+//! `--listen <addr>` to use another address. Bodies over
+//! `--max-content-length <MiB>` (default 10) are rejected with 413. New items can be pushed with
+//! `curl -X POST -H 'X-Type: text' -d hello http://127.0.0.1:3376/api/v1/clipboard`.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// Stored clipboard item
+#[derive(Clone, Default)]
+struct Item {
+    index: u64,
+    kind: String,
+    file_name: String,
+    client_name: String,
+    mime_type: String,
+    created_at: u64,
+    data: Vec<u8>,
+}
+
+/// Shared server state
+struct Server {
+    polling_only: bool,
+    /// Maximum body size in bytes
+    max_content_length: usize,
+    item: Mutex<Item>,
+    index: watch::Sender<u64>,
+}
+
+/// Parsed request
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    /// The announced body exceeds the limit and was not read
+    too_large: bool,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> &str {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_default()
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let polling_only = args.iter().any(|arg| arg == "--polling-only");
+    let listen = args
+        .iter()
+        .position(|arg| arg == "--listen")
+        .and_then(|i| args.get(i + 1))
+        .map_or("127.0.0.1:3376", String::as_str);
+    let max_content_length_mib: usize = args
+        .iter()
+        .position(|arg| arg == "--max-content-length")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(10);
+
+    let server = Arc::new(Server {
+        polling_only,
+        max_content_length: max_content_length_mib * 1024 * 1024,
+        item: Mutex::new(Item::default()),
+        index: watch::channel(0).0,
+    });
+
+    let listener = TcpListener::bind(listen).await?;
+    println!(
+        "Test server listening on http://{} ({})",
+        listen,
+        if polling_only {
+            "polling only"
+        } else {
+            "push channel enabled"
+        }
+    );
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, server).await {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+    }
+}
+
+/// Serve a single request; the connection is closed afterwards
+async fn handle(stream: TcpStream, server: Arc<Server>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let Some(request) = read_request(&mut reader, server.max_content_length).await? else {
+        return Ok(());
+    };
+    let mut stream = reader.into_inner();
+
+    println!("{} {}", request.method, request.path);
+
+    if request.too_large {
+        let body = format!(
+            r#"{{"message":"The synchronized content length cannot exceed {}MiB"}}"#,
+            server.max_content_length / 1024 / 1024
+        );
+        let headers = [("Content-Type", "application/json")];
+        return respond(&mut stream, 413, &headers, body.as_bytes()).await;
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/v1/ping") => respond(&mut stream, 200, &[], b"pong").await,
+        ("GET", "/api/v1/systeminfo") => {
+            let body = format!(
+                r#"{{"version":"v1.5.0","maxContentLength":{}}}"#,
+                server.max_content_length
+            );
+            let headers = [("Content-Type", "application/json")];
+            respond(&mut stream, 200, &headers, body.as_bytes()).await
+        }
+        ("GET", "/api/v1/user") => {
+            let body = br#"{"email":"test@localhost","loggedIn":true}"#;
+            respond(
+                &mut stream,
+                200,
+                &[("Content-Type", "application/json")],
+                body,
+            )
+            .await
+        }
+        ("GET", "/api/v1/clipboard") => get_clipboard(&mut stream, &server, &request).await,
+        ("POST", "/api/v1/clipboard") => post_clipboard(&mut stream, &server, request).await,
+        ("GET", "/api/v1/clipboard/events") if !server.polling_only => {
+            stream_events(&mut stream, &server).await
+        }
+        _ => {
+            let body = br#"{"message":"Not found"}"#;
+            respond(
+                &mut stream,
+                404,
+                &[("Content-Type", "application/json")],
+                body,
+            )
+            .await
+        }
+    }
+}
+
+async fn get_clipboard(
+    stream: &mut TcpStream,
+    server: &Server,
+    request: &Request,
+) -> std::io::Result<()> {
+    let item = server
+        .item
+        .lock()
+        .map(|item| item.clone())
+        .unwrap_or_default();
+    let index = item.index.to_string();
+
+    if item.index == 0 || request.header("X-Index") == index {
+        return respond(stream, 200, &[("X-Index", &index)], b"").await;
+    }
+
+    let created_at = item.created_at.to_string();
+    let headers = [
+        ("Content-Type", item.mime_type.as_str()),
+        ("X-Index", index.as_str()),
+        ("X-Type", item.kind.as_str()),
+        ("X-FileName", item.file_name.as_str()),
+        ("X-ClientName", item.client_name.as_str()),
+        ("X-CreatedAt", created_at.as_str()),
+    ];
+    respond(stream, 200, &headers, &item.data).await
+}
+
+async fn post_clipboard(
+    stream: &mut TcpStream,
+    server: &Server,
+    request: Request,
+) -> std::io::Result<()> {
+    let kind = request.header("X-Type").to_string();
+    if kind.is_empty() {
+        let body = br#"{"message":"Request header invalid"}"#;
+        return respond(stream, 400, &[("Content-Type", "application/json")], body).await;
+    }
+
+    let index = {
+        let Ok(mut item) = server.item.lock() else {
+            return respond(stream, 500, &[], b"").await;
+        };
+        *item = Item {
+            index: item.index + 1,
+            kind,
+            file_name: request.header("X-FileName").to_string(),
+            client_name: request.header("X-ClientName").to_string(),
+            mime_type: request.header("Content-Type").to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            data: request.body,
+        };
+        item.index
+    };
+    server.index.send_replace(index);
+
+    let index = index.to_string();
+    let headers = [("Content-Type", "application/json"), ("X-Index", &index)];
+    respond(stream, 200, &headers, br#"{"message":"Success"}"#).await
+}
+
+/// Announce new indexes until the client goes away
+async fn stream_events(stream: &mut TcpStream, server: &Server) -> std::io::Result<()> {
+    let mut index = server.index.subscribe();
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        )
+        .await?;
+
+    loop {
+        let current = *index.borrow_and_update();
+        stream
+            .write_all(format!("event:index\ndata:{}\n\n", current).as_bytes())
+            .await?;
+
+        tokio::select! {
+            changed = index.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+            }
+            _ = tokio::time::sleep(Duration::from_secs(30)) => {
+                stream.write_all(b": keepalive\n\n").await?;
+            }
+        }
+    }
+}
+
+async fn read_request(
+    reader: &mut BufReader<TcpStream>,
+    max_content_length: usize,
+) -> std::io::Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+        too_large: false,
+    };
+
+    let length: usize = request.header("Content-Length").parse().unwrap_or(0);
+    if length > max_content_length {
+        request.too_large = true;
+        return Ok(Some(request));
+    }
+    request.body.resize(length, 0);
+    reader.read_exact(&mut request.body).await?;
+
+    Ok(Some(request))
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    status: u16,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason);
+    for (key, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.flush().await
+}
@@ -0,0 +1,212 @@
+//! Account login
+//!
+//! Email-code login against the server. The session cookie is kept in the
+//! secrets store so the device stays logged in across restarts.
+
+use crate::error::AppError;
+use crate::secrets;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Logged-in user as returned by the server
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerUser {
+    pub(crate) email: String,
+    pub(crate) logged_in: bool,
+}
+
+/// Login state for frontend
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    pub email: Option<String>,
+    pub logged_in: bool,
+}
+
+impl UserInfo {
+    fn from_account(account: Option<String>) -> Self {
+        Self {
+            logged_in: account.is_some(),
+            email: account,
+        }
+    }
+}
+
+/// Secrets store entry holding the session cookie of a server
+fn session_secret(server_url: &str) -> String {
+    format!("session:{}", server_url.trim_end_matches('/'))
+}
+
+/// Load the persisted session cookie of a server into the jar
+pub fn restore_session(jar: &Jar, server_url: &str) {
+    let Ok(url) = server_url.parse::<Url>() else {
+        return;
+    };
+
+    match secrets::get(&session_secret(server_url)) {
+        Ok(Some(cookies)) => {
+            for cookie in cookies.split("; ") {
+                jar.add_cookie_str(cookie, &url);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to restore session: {}", e),
+    }
+}
+
+/// Persist the session cookie currently held for a server
+fn persist_session(jar: &Jar, server_url: &str) -> Result<(), AppError> {
+    let url = server_url
+        .parse::<Url>()
+        .map_err(|e| AppError::Config(e.to_string()))?;
+
+    match jar.cookies(&url) {
+        Some(cookies) => {
+            let cookies = cookies
+                .to_str()
+                .map_err(|e| AppError::Protocol(e.to_string()))?;
+            secrets::set(&session_secret(server_url), cookies)
+        }
+        None => secrets::delete(&session_secret(server_url)),
+    }
+}
+
+/// Refresh the logged-in account from the server.
+///
+/// Failures are not fatal; the account simply stays unknown.
+pub async fn refresh_account(app: &AppHandle) -> Option<String> {
+    let state = app.state::<crate::AppState>();
+    let server_url = state.config.lock().await.server_url.clone();
+
+    let resp = match state
+        .sync_state
+        .client()
+        .get(format!("{}/api/v1/user", server_url))
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            log::debug!("Failed to fetch account: {}", e);
+            return state.sync_state.account();
+        }
+    };
+
+    let account = if resp.status().is_success() {
+        resp.json::<ServerUser>()
+            .await
+            .ok()
+            .filter(|user| user.logged_in)
+            .map(|user| user.email)
+    } else {
+        None
+    };
+
+    // The server renews the session cookie on every lookup
+    if account.is_some() {
+        if let Err(e) = persist_session(&state.sync_state.cookies, &server_url) {
+            log::error!("Failed to persist session: {}", e);
+        }
+    }
+
+    state.sync_state.set_account(account.clone());
+    crate::tray::refresh_status(app);
+    account
+}
+
+/// Tauri command: Send a login code to an email address
+#[tauri::command]
+pub async fn request_login_code(app: AppHandle, email: String) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+
+    let resp = state
+        .sync_state
+        .client()
+        .post(format!(
+            "{}/api/v1/user/email-code",
+            state.sync_state.server_url()
+        ))
+        .json(&serde_json::json!({ "email": email }))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        return Err(AppError::from_response(resp).await);
+    }
+
+    Ok(())
+}
+
+/// Tauri command: Log in with the code received by email
+#[tauri::command]
+pub async fn login(app: AppHandle, email: String, code: String) -> Result<UserInfo, AppError> {
+    let state = app.state::<crate::AppState>();
+    let server_url = state.sync_state.server_url();
+
+    let resp = state
+        .sync_state
+        .client()
+        .post(format!("{}/api/v1/user/login", server_url))
+        .json(&serde_json::json!({ "email": email, "code": code }))
+        .send()
+        .await?;
+
+    // The server answers 401 when the code is wrong or expired
+    if resp.status() == StatusCode::UNAUTHORIZED {
+        return Err(AppError::Auth("Invalid or expired login code".into()));
+    }
+    if !resp.status().is_success() {
+        return Err(AppError::from_response(resp).await);
+    }
+
+    let user: ServerUser = resp.json().await?;
+    if !user.logged_in {
+        return Err(AppError::Auth("Login failed".into()));
+    }
+
+    persist_session(&state.sync_state.cookies, &server_url)?;
+    state.sync_state.set_account(Some(user.email.clone()));
+    crate::tray::refresh_status(&app);
+
+    // Resume syncing right away instead of waiting out the backoff
+    state.sync_state.connection.retry_now();
+    state.sync_state.push_channel.reconnect();
+
+    log::info!("Logged in as {}", user.email);
+
+    Ok(UserInfo::from_account(Some(user.email)))
+}
+
+/// Tauri command: Log out and forget the stored session
+#[tauri::command]
+pub async fn logout(app: AppHandle) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+    let server_url = state.sync_state.server_url();
+
+    if let Err(e) = state
+        .sync_state
+        .client()
+        .get(format!("{}/api/v1/user/logout", server_url))
+        .send()
+        .await
+    {
+        log::warn!("Server logout failed: {}", e);
+    }
+
+    secrets::delete(&session_secret(&server_url))?;
+    state.sync_state.set_account(None);
+    crate::tray::refresh_status(&app);
+
+    log::info!("Logged out");
+
+    Ok(())
+}
+
+/// Tauri command: Get the logged-in user
+#[tauri::command]
+pub async fn get_user(app: AppHandle) -> UserInfo {
+    UserInfo::from_account(refresh_account(&app).await)
+}
@@ -2,12 +2,14 @@
 //!
 //! This module handles native clipboard access using the `arboard` crate.
 
+use crate::error::AppError;
+use crate::fingerprint::Fingerprint;
 use arboard::Clipboard;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Content types that can be stored in clipboard
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -18,39 +20,40 @@ pub enum ClipboardContent {
 }
 
 /// Global clipboard state for change detection
-static LAST_CLIPBOARD_HASH: AtomicU64 = AtomicU64::new(0);
+static LAST_FINGERPRINT: Mutex<Option<Fingerprint>> = Mutex::new(None);
+static LAST_LOCAL_CHANGE_AT: AtomicU64 = AtomicU64::new(0);
 static CLIPBOARD_MUTEX: Mutex<()> = Mutex::new(());
 
-/// Calculate a simple hash of clipboard content for change detection
-fn hash_content(content: &ClipboardContent) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+/// Fingerprint of the clipboard content last seen or written
+pub fn last_fingerprint() -> Option<Fingerprint> {
+    LAST_FINGERPRINT.lock().ok().and_then(|last| *last)
+}
 
-    let mut hasher = DefaultHasher::new();
-    match content {
-        ClipboardContent::Text(text) => {
-            "text".hash(&mut hasher);
-            text.hash(&mut hasher);
-        }
-        ClipboardContent::Image(data) => {
-            "image".hash(&mut hasher);
-            data.hash(&mut hasher);
-        }
+/// Unix time in milliseconds of the last change made locally by the user
+pub fn last_local_change_at() -> u64 {
+    LAST_LOCAL_CHANGE_AT.load(Ordering::SeqCst)
+}
+
+/// Restore the last seen fingerprint, e.g. from a persisted sync cursor
+pub fn set_last_fingerprint(fingerprint: Option<Fingerprint>) {
+    if let Ok(mut last) = LAST_FINGERPRINT.lock() {
+        *last = fingerprint;
     }
-    hasher.finish()
 }
 
 /// Read current clipboard content
 #[tauri::command]
-pub fn read_clipboard() -> Result<ClipboardContent, String> {
-    let _lock = CLIPBOARD_MUTEX.lock().map_err(|e| e.to_string())?;
+pub fn read_clipboard() -> Result<ClipboardContent, AppError> {
+    let _lock = CLIPBOARD_MUTEX
+        .lock()
+        .map_err(|e| AppError::Clipboard(e.to_string()))?;
 
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    let mut clipboard = Clipboard::new()?;
 
     // Try to get image first
     if let Ok(img) = clipboard.get_image() {
         // Convert to PNG
-        let png_data = image_to_png(&img).map_err(|e| e.to_string())?;
+        let png_data = image_to_png(&img)?;
         return Ok(ClipboardContent::Image(png_data));
     }
 
@@ -61,41 +64,42 @@ pub fn read_clipboard() -> Result<ClipboardContent, String> {
         }
     }
 
-    Err("Clipboard is empty or contains unsupported format".into())
+    Err(AppError::ClipboardEmpty)
 }
 
 /// Write content to clipboard
 #[tauri::command]
-pub fn write_clipboard(content: ClipboardContent) -> Result<(), String> {
-    let _lock = CLIPBOARD_MUTEX.lock().map_err(|e| e.to_string())?;
+pub fn write_clipboard(content: ClipboardContent) -> Result<(), AppError> {
+    let _lock = CLIPBOARD_MUTEX
+        .lock()
+        .map_err(|e| AppError::Clipboard(e.to_string()))?;
 
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    let mut clipboard = Clipboard::new()?;
 
     match content {
         ClipboardContent::Text(text) => {
-            clipboard.set_text(&text).map_err(|e| e.to_string())?;
+            clipboard.set_text(&text)?;
         }
         ClipboardContent::Image(data) => {
-            let img = png_to_image(&data).map_err(|e| e.to_string())?;
-            clipboard.set_image(img).map_err(|e| e.to_string())?;
+            let img = png_to_image(&data)?;
+            clipboard.set_image(img)?;
         }
     }
 
-    // Update hash to prevent re-triggering sync
+    // Update fingerprint to prevent re-triggering sync
     if let Ok(current) = read_clipboard_internal() {
-        let hash = hash_content(&current);
-        LAST_CLIPBOARD_HASH.store(hash, Ordering::SeqCst);
+        set_last_fingerprint(Some(Fingerprint::of(&current)));
     }
 
     Ok(())
 }
 
 /// Internal clipboard read without locking (for use within locked context)
-fn read_clipboard_internal() -> Result<ClipboardContent, String> {
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+fn read_clipboard_internal() -> Result<ClipboardContent, AppError> {
+    let mut clipboard = Clipboard::new()?;
 
     if let Ok(img) = clipboard.get_image() {
-        let png_data = image_to_png(&img).map_err(|e| e.to_string())?;
+        let png_data = image_to_png(&img)?;
         return Ok(ClipboardContent::Image(png_data));
     }
 
@@ -105,11 +109,11 @@ fn read_clipboard_internal() -> Result<ClipboardContent, String> {
         }
     }
 
-    Err("Clipboard is empty".into())
+    Err(AppError::ClipboardEmpty)
 }
 
 /// Convert arboard ImageData to PNG bytes
-fn image_to_png(img: &arboard::ImageData) -> Result<Vec<u8>, String> {
+fn image_to_png(img: &arboard::ImageData) -> Result<Vec<u8>, AppError> {
     use std::io::Cursor;
 
     let width = img.width as u32;
@@ -122,24 +126,30 @@ fn image_to_png(img: &arboard::ImageData) -> Result<Vec<u8>, String> {
         encoder.set_color(png::ColorType::Rgba);
         encoder.set_depth(png::BitDepth::Eight);
 
-        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| AppError::Clipboard(e.to_string()))?;
         writer
             .write_image_data(&img.bytes)
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| AppError::Clipboard(e.to_string()))?;
     }
 
     Ok(png_data)
 }
 
 /// Convert PNG bytes to arboard ImageData
-fn png_to_image(data: &[u8]) -> Result<arboard::ImageData<'static>, String> {
+fn png_to_image(data: &[u8]) -> Result<arboard::ImageData<'static>, AppError> {
     use std::io::Cursor;
 
     let decoder = png::Decoder::new(Cursor::new(data));
-    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| AppError::Clipboard(e.to_string()))?;
 
     let mut buf = vec![0; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| AppError::Clipboard(e.to_string()))?;
 
     // Ensure RGBA format
     let bytes = if info.color_type == png::ColorType::Rgba {
@@ -181,18 +191,31 @@ pub fn start_clipboard_monitor(app: AppHandle) {
             }
         };
 
-        let hash = hash_content(&content);
-        let last_hash = LAST_CLIPBOARD_HASH.load(Ordering::SeqCst);
+        let fingerprint = Fingerprint::of(&content);
+
+        if last_fingerprint() != Some(fingerprint) {
+            set_last_fingerprint(Some(fingerprint));
+            LAST_LOCAL_CHANGE_AT.store(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or_default(),
+                Ordering::SeqCst,
+            );
+
+            // Poll more often while the user is copying
+            if let Some(state) = app.try_state::<crate::AppState>() {
+                state.sync_state.schedule.activity();
+            }
 
-        if hash != last_hash {
-            LAST_CLIPBOARD_HASH.store(hash, Ordering::SeqCst);
+            crate::recent::record(&app, content.clone(), None);
 
             // Emit event to frontend
             if let Err(e) = app.emit("clipboard-changed", &content) {
                 log::error!("Failed to emit clipboard-changed event: {}", e);
             }
 
-            log::debug!("Clipboard changed, new hash: {}", hash);
+            log::debug!("Clipboard changed, fingerprint: {}", fingerprint.short());
         }
     }
 }
@@ -0,0 +1,296 @@
+//! Payload compression
+//!
+//! Payloads can be compressed before they are encrypted and pushed. The
+//! algorithm is named in a parameter of the Content-Type the item is stored
+//! with, next to the end-to-end encryption type, so every desktop client
+//! decompresses it on pull whatever its own setting, and payloads that merely
+//! look compressed are left alone. Formats that are compressed already, such
+//! as PNG screenshots or zip archives, are sent as they are.
+//!
+//! ```text
+//! application/x-gcopy-encrypted; compression=zstd
+//! application/octet-stream; compression=gzip
+//! ```
+
+use crate::config::{CompressionAlgorithm, CompressionConfig};
+use crate::error::AppError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{Read, Write};
+
+/// Content-Type parameter naming the compression algorithm
+const PARAMETER: &str = "compression";
+
+/// Largest payload a pulled item may decompress to
+const MAX_DECOMPRESSED: u64 = 1 << 30;
+
+/// zstd compression level; fast with a good ratio for text
+const ZSTD_LEVEL: i32 = 3;
+
+/// Leading bytes of formats that are compressed already
+const COMPRESSED_SIGNATURES: &[&[u8]] = &[
+    b"\x89PNG",
+    b"\xff\xd8\xff",
+    b"GIF8",
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"\x28\xb5\x2f\xfd",
+    b"7z\xbc\xaf",
+    b"Rar!",
+    b"\xfd7zXZ",
+    b"BZh",
+];
+
+/// Whether the payload is in a format that does not compress further
+fn is_compressed_format(data: &[u8]) -> bool {
+    let is_webp = data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP";
+    let is_mp4 = data.len() >= 8 && &data[4..8] == b"ftyp";
+
+    is_webp
+        || is_mp4
+        || COMPRESSED_SIGNATURES
+            .iter()
+            .any(|signature| data.starts_with(signature))
+}
+
+/// Content-Type of a payload compressed with `algorithm`
+pub fn content_type(mime_type: &str, algorithm: CompressionAlgorithm) -> String {
+    match algorithm {
+        CompressionAlgorithm::Off => mime_type.to_string(),
+        CompressionAlgorithm::Zstd => format!("{}; {}=zstd", mime_type, PARAMETER),
+        CompressionAlgorithm::Gzip => format!("{}; {}=gzip", mime_type, PARAMETER),
+    }
+}
+
+/// MIME type and compression algorithm of a pulled item's Content-Type
+pub fn parse_content_type(content_type: &str) -> Result<(String, CompressionAlgorithm), AppError> {
+    let mut parts = content_type.split(';');
+    let mime_type = parts.next().unwrap_or_default().trim().to_string();
+
+    let mut algorithm = CompressionAlgorithm::Off;
+    for parameter in parts {
+        let Some((name, value)) = parameter.split_once('=') else {
+            continue;
+        };
+        if !name.trim().eq_ignore_ascii_case(PARAMETER) {
+            continue;
+        }
+        algorithm = match value.trim().trim_matches('"').to_ascii_lowercase().as_str() {
+            "zstd" => CompressionAlgorithm::Zstd,
+            "gzip" => CompressionAlgorithm::Gzip,
+            other => {
+                return Err(AppError::Protocol(format!(
+                    "Unsupported payload compression {}",
+                    other
+                )))
+            }
+        };
+    }
+
+    Ok((mime_type, algorithm))
+}
+
+/// Compress a payload for pushing if it is worth it; returns the payload and
+/// the algorithm it ended up compressed with
+pub async fn compress(
+    data: Vec<u8>,
+    config: &CompressionConfig,
+) -> Result<(Vec<u8>, CompressionAlgorithm), AppError> {
+    let config = config.clone();
+    blocking(move || Ok(compress_blocking(data, &config))).await
+}
+
+fn compress_blocking(data: Vec<u8>, config: &CompressionConfig) -> (Vec<u8>, CompressionAlgorithm) {
+    if config.algorithm == CompressionAlgorithm::Off
+        || data.len() < config.min_size
+        || is_compressed_format(&data)
+    {
+        return (data, CompressionAlgorithm::Off);
+    }
+
+    let compressed = match config.algorithm {
+        CompressionAlgorithm::Off => return (data, CompressionAlgorithm::Off),
+        CompressionAlgorithm::Zstd => zstd::encode_all(data.as_slice(), ZSTD_LEVEL),
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data).and_then(|()| encoder.finish())
+        }
+    };
+
+    match compressed {
+        // Not worth the decompression on the other side
+        Ok(compressed) if compressed.len() < data.len() => {
+            log::debug!(
+                "Compressed payload from {} to {} bytes",
+                data.len(),
+                compressed.len()
+            );
+            (compressed, config.algorithm)
+        }
+        Ok(_) => (data, CompressionAlgorithm::Off),
+        Err(e) => {
+            log::warn!("Failed to compress payload, sending it as is: {}", e);
+            (data, CompressionAlgorithm::Off)
+        }
+    }
+}
+
+/// Decompress a pulled payload stored with `algorithm`
+pub async fn decompress(
+    data: Vec<u8>,
+    algorithm: CompressionAlgorithm,
+) -> Result<Vec<u8>, AppError> {
+    if algorithm == CompressionAlgorithm::Off {
+        return Ok(data);
+    }
+    blocking(move || decompress_blocking(data, algorithm)).await
+}
+
+fn decompress_blocking(
+    data: Vec<u8>,
+    algorithm: CompressionAlgorithm,
+) -> Result<Vec<u8>, AppError> {
+    let mut output = Vec::new();
+    let result = match algorithm {
+        CompressionAlgorithm::Off => return Ok(data),
+        CompressionAlgorithm::Zstd => zstd::stream::read::Decoder::new(data.as_slice())
+            .and_then(|decoder| decoder.take(MAX_DECOMPRESSED + 1).read_to_end(&mut output)),
+        CompressionAlgorithm::Gzip => GzDecoder::new(data.as_slice())
+            .take(MAX_DECOMPRESSED + 1)
+            .read_to_end(&mut output),
+    };
+
+    result.map_err(|e| AppError::Protocol(format!("Failed to decompress payload: {}", e)))?;
+    if output.len() as u64 > MAX_DECOMPRESSED {
+        return Err(AppError::Protocol(
+            "Decompressed payload is too large".into(),
+        ));
+    }
+    Ok(output)
+}
+
+/// Run compression on a blocking thread
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Protocol(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::async_runtime::block_on;
+
+    fn config(algorithm: CompressionAlgorithm) -> CompressionConfig {
+        CompressionConfig {
+            algorithm,
+            min_size: 16,
+        }
+    }
+
+    fn text() -> Vec<u8> {
+        "the quick brown fox jumps over the lazy dog\n"
+            .repeat(100)
+            .into_bytes()
+    }
+
+    fn round_trip(algorithm: CompressionAlgorithm) {
+        let (compressed, used) = block_on(compress(text(), &config(algorithm))).unwrap();
+        assert_eq!(used, algorithm);
+        assert!(compressed.len() < text().len());
+
+        let stored = content_type("application/octet-stream", used);
+        let (mime_type, parsed) = parse_content_type(&stored).unwrap();
+        assert_eq!(mime_type, "application/octet-stream");
+        assert_eq!(parsed, algorithm);
+        assert_eq!(block_on(decompress(compressed, parsed)).unwrap(), text());
+    }
+
+    #[test]
+    fn zstd_round_trip() {
+        round_trip(CompressionAlgorithm::Zstd);
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        round_trip(CompressionAlgorithm::Gzip);
+    }
+
+    #[test]
+    fn off_and_small_payloads_are_sent_as_they_are() {
+        let off = compress_blocking(text(), &config(CompressionAlgorithm::Off));
+        assert_eq!(off, (text(), CompressionAlgorithm::Off));
+        let small = b"short".to_vec();
+        assert_eq!(
+            compress_blocking(small.clone(), &config(CompressionAlgorithm::Zstd)),
+            (small, CompressionAlgorithm::Off)
+        );
+    }
+
+    #[test]
+    fn compressed_formats_are_skipped() {
+        let zstd = config(CompressionAlgorithm::Zstd);
+
+        for prefix in [
+            b"\x89PNG\r\n\x1a\n".as_slice(),
+            b"RIFF\0\0\0\0WEBP",
+            b"PK\x03\x04",
+        ] {
+            let data = [prefix, &text()].concat();
+            assert_eq!(
+                compress_blocking(data.clone(), &zstd),
+                (data, CompressionAlgorithm::Off)
+            );
+        }
+    }
+
+    #[test]
+    fn only_flagged_payloads_are_decompressed() {
+        // A plain payload that happens to be a zstd frame stays as it is
+        let (frame, _) = compress_blocking(text(), &config(CompressionAlgorithm::Zstd));
+        let (_, algorithm) = parse_content_type("application/octet-stream").unwrap();
+        assert_eq!(
+            block_on(decompress(frame.clone(), algorithm)).unwrap(),
+            frame
+        );
+        assert!(block_on(decompress(Vec::new(), algorithm))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parses_content_type_parameters() {
+        let parsed =
+            parse_content_type("application/x-gcopy-encrypted;Compression=\"GZIP\"").unwrap();
+        assert_eq!(
+            parsed,
+            (
+                "application/x-gcopy-encrypted".to_string(),
+                CompressionAlgorithm::Gzip
+            )
+        );
+        let parsed = parse_content_type("text/plain; charset=utf-8").unwrap();
+        assert_eq!(
+            parsed,
+            ("text/plain".to_string(), CompressionAlgorithm::Off)
+        );
+        assert_eq!(
+            parse_content_type("").unwrap(),
+            (String::new(), CompressionAlgorithm::Off)
+        );
+        assert!(matches!(
+            parse_content_type("application/octet-stream; compression=brotli"),
+            Err(AppError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn corrupt_payloads_are_rejected() {
+        for algorithm in [CompressionAlgorithm::Zstd, CompressionAlgorithm::Gzip] {
+            let result = block_on(decompress(b"not compressed".to_vec(), algorithm));
+            assert!(matches!(result, Err(AppError::Protocol(_))));
+        }
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! Handles loading, saving, and accessing application configuration.
 
+use crate::error::AppError;
+use crate::secrets;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,14 +23,71 @@ pub struct AppConfig {
     /// Sync interval in seconds
     pub sync_interval: u64,
 
+    /// Slower polling while idle
+    #[serde(default)]
+    pub polling: PollingConfig,
+
     /// Enable auto-start on system boot
     pub auto_start: bool,
 
     /// Sync content types
     pub sync_types: SyncTypes,
 
+    /// Whether this device sends, receives or both
+    #[serde(default)]
+    pub direction: SyncDirection,
+
+    /// Per content type directions overriding `direction`
+    #[serde(default)]
+    pub direction_overrides: DirectionOverrides,
+
     /// Keyboard shortcuts
     pub shortcuts: Shortcuts,
+
+    /// End-to-end encryption settings
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+
+    /// Compression of pushed payloads
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// Reconnect behavior while the server is unreachable
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
+
+    /// Queue for pushes made while offline
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+
+    /// Server push channel used instead of interval polling
+    #[serde(default)]
+    pub push_channel: PushChannelConfig,
+
+    /// What to do when local and remote both changed since the last sync
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+
+    /// Proxy, certificates and timeouts of server connections
+    #[serde(default)]
+    pub network: NetworkConfig,
+
+    /// Headers and credentials required by reverse proxies, per server
+    #[serde(default)]
+    pub server_access: Vec<ServerAccess>,
+
+    /// Name other devices see on items pushed from here; empty uses the
+    /// host name
+    #[serde(default)]
+    pub device_name: String,
+
+    /// Desktop notifications
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+
+    /// Recent items in the tray
+    #[serde(default)]
+    pub recent: RecentConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,49 +105,522 @@ pub struct Shortcuts {
     pub toggle_window: String,
 }
 
+/// Direction in which content is synced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncDirection {
+    /// Push local changes and pull remote ones
+    #[default]
+    Bidirectional,
+    /// Only push local changes
+    SendOnly,
+    /// Only pull remote changes
+    ReceiveOnly,
+}
+
+impl SyncDirection {
+    /// Whether local content may be pushed
+    pub fn can_send(self) -> bool {
+        self != Self::ReceiveOnly
+    }
+
+    /// Whether remote content may be pulled
+    pub fn can_receive(self) -> bool {
+        self != Self::SendOnly
+    }
+}
+
+/// Per content type directions; unset types follow the global direction
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DirectionOverrides {
+    pub text: Option<SyncDirection>,
+    pub screenshot: Option<SyncDirection>,
+    pub file: Option<SyncDirection>,
+}
+
+/// End-to-end encryption settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionConfig {
+    /// Encrypt pushed payloads and decrypt pulled ones
+    pub enabled: bool,
+
+    /// Shared passphrase the encryption key is derived from.
+    ///
+    /// Kept in the secrets store; never written to the config file.
+    pub passphrase: String,
+
+    /// Refuse unencrypted payloads pulled from the server
+    pub require_encrypted: bool,
+}
+
+impl EncryptionConfig {
+    /// Passphrase to use, if encryption is enabled and configured
+    pub fn active_passphrase(&self) -> Option<&str> {
+        if self.enabled && !self.passphrase.is_empty() {
+            Some(&self.passphrase)
+        } else {
+            None
+        }
+    }
+}
+
+/// Compression of pushed payloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompressionConfig {
+    /// Algorithm used for pushes; pulled payloads are always decompressed
+    pub algorithm: CompressionAlgorithm,
+
+    /// Payloads smaller than this many bytes are sent as they are
+    pub min_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: CompressionAlgorithm::default(),
+            min_size: 1024,
+        }
+    }
+}
+
+/// Compression algorithm of pushed payloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompressionAlgorithm {
+    /// Send payloads as they are, readable by the web client
+    #[default]
+    Off,
+    Zstd,
+    Gzip,
+}
+
+/// Reconnect behavior while the server is unreachable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReconnectConfig {
+    /// Delay after the first failure in seconds, doubled on each failure
+    pub initial_delay_secs: u64,
+
+    /// Upper bound of the delay in seconds
+    pub max_delay_secs: u64,
+
+    /// Random spread applied to each delay, as a fraction (0.2 = ±20%)
+    pub jitter: f64,
+
+    /// Consecutive failures before the server is considered offline
+    pub offline_after_failures: u32,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay_secs: 2,
+            max_delay_secs: 300,
+            jitter: 0.2,
+            offline_after_failures: 3,
+        }
+    }
+}
+
+/// Queue for pushes made while offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutboxConfig {
+    /// Queue failed pushes and retry them when the server is back
+    pub enabled: bool,
+
+    /// Keep only the newest queued item per content type
+    pub collapse: bool,
+
+    /// Maximum number of queued items; older ones are dropped (0 = no limit)
+    pub max_items: usize,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            collapse: true,
+            max_items: 20,
+        }
+    }
+}
+
+/// Which desktop notifications are shown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationConfig {
+    /// Items pulled from another device
+    pub received: bool,
+
+    /// Pushes that failed and were not queued
+    pub push_failed: bool,
+
+    /// The server no longer accepts the session
+    pub auth_expired: bool,
+
+    /// Items too large for the server
+    pub too_large: bool,
+
+    /// Conflicts waiting for a decision
+    pub conflict: bool,
+
+    /// Include a text preview or image thumbnail of received items
+    pub previews: bool,
+
+    /// Show no notifications at all
+    pub do_not_disturb: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            received: true,
+            push_failed: true,
+            auth_expired: true,
+            too_large: true,
+            conflict: true,
+            previews: true,
+            do_not_disturb: false,
+        }
+    }
+}
+
+/// Recent items listed in the tray
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecentConfig {
+    /// How many items are kept (0 = none)
+    pub max_items: usize,
+
+    /// Push an item again when it is picked from the list
+    pub repush: bool,
+}
+
+impl Default for RecentConfig {
+    fn default() -> Self {
+        Self {
+            max_items: 10,
+            repush: false,
+        }
+    }
+}
+
+/// Slower polling while idle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PollingConfig {
+    /// Grow the poll interval while nothing changes
+    pub adaptive: bool,
+
+    /// Longest poll interval in seconds
+    pub max_interval_secs: u64,
+
+    /// Growth of the interval on each idle poll
+    pub backoff_factor: f64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            adaptive: true,
+            max_interval_secs: 60,
+            backoff_factor: 1.5,
+        }
+    }
+}
+
+/// Server push channel used instead of interval polling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PushChannelConfig {
+    /// Listen for new items announced by the server
+    pub enabled: bool,
+
+    /// Poll interval in seconds while the channel is connected, as a safety net
+    pub poll_interval_secs: u64,
+}
+
+impl Default for PushChannelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: 60,
+        }
+    }
+}
+
+/// Resolution of a sync conflict
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    /// Keep the local clipboard and push it over the remote item
+    PreferLocal,
+    /// Replace the local clipboard with the remote item
+    PreferRemote,
+    /// Keep whichever side changed last
+    #[default]
+    Newest,
+    /// Keep both untouched and ask the user
+    Ask,
+}
+
+/// Proxy, certificates and timeouts of server connections
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkConfig {
+    /// Proxy URL (`http://`, `https://` or `socks5://`); empty uses the system proxy
+    pub proxy: String,
+
+    /// Comma separated hosts reached without the proxy
+    pub no_proxy: String,
+
+    /// How server certificates are verified
+    pub certificate_trust: CertificateTrust,
+
+    /// PEM files with extra trusted CA certificates
+    pub ca_certificates: Vec<String>,
+
+    /// PEM file with a client certificate for mutual TLS
+    pub client_certificate: String,
+
+    /// PEM file with the PKCS#8 private key of the client certificate
+    pub client_key: String,
+
+    /// Connect timeout in seconds
+    pub connect_timeout_secs: u64,
+
+    /// Longest wait for data on an open connection in seconds (0 = no limit)
+    pub read_timeout_secs: u64,
+}
+
+/// Headers and credentials a reverse proxy in front of a server requires
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerAccess {
+    /// Server the settings apply to
+    pub server_url: String,
+
+    /// Extra headers sent with every request
+    pub headers: Vec<CustomHeader>,
+
+    /// HTTP basic auth user name; empty for none
+    pub username: String,
+
+    /// HTTP basic auth password
+    pub password: String,
+}
+
+/// Request header; the value is kept in the secrets store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CustomHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// Secret parts of a [`ServerAccess`], stored by server URL
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AccessSecrets {
+    header_values: Vec<String>,
+    password: String,
+}
+
+/// How server certificates are verified
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CertificateTrust {
+    /// Against the system CA roots and `ca_certificates`
+    #[default]
+    System,
+    /// Pin the certificate seen first and refuse any other
+    TrustOnFirstUse,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: String::new(),
+            no_proxy: String::new(),
+            certificate_trust: CertificateTrust::default(),
+            ca_certificates: Vec::new(),
+            client_certificate: String::new(),
+            client_key: String::new(),
+            connect_timeout_secs: 10,
+            // Longer than the push channel keepalive
+            read_timeout_secs: 60,
+        }
+    }
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             server_url: "https://gcopy.llaoj.cn".into(),
             auto_sync: true,
             sync_interval: 3,
+            polling: PollingConfig::default(),
             auto_start: false,
             sync_types: SyncTypes {
                 text: true,
                 screenshot: true,
                 file: true,
             },
+            direction: SyncDirection::default(),
+            direction_overrides: DirectionOverrides::default(),
             shortcuts: Shortcuts {
                 manual_sync: "CmdOrCtrl+Shift+V".into(),
                 toggle_window: "CmdOrCtrl+Shift+G".into(),
             },
+            encryption: EncryptionConfig::default(),
+            compression: CompressionConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            outbox: OutboxConfig::default(),
+            push_channel: PushChannelConfig::default(),
+            conflict_policy: ConflictPolicy::default(),
+            network: NetworkConfig::default(),
+            server_access: Vec::new(),
+            device_name: String::new(),
+            notifications: NotificationConfig::default(),
+            recent: RecentConfig::default(),
         }
     }
 }
 
+/// Secrets store entry holding the encryption passphrase
+const PASSPHRASE_SECRET: &str = "encryption-passphrase";
+
+/// Secrets store entry holding header values and passwords of servers
+const SERVER_ACCESS_SECRET: &str = "server-access";
+
 impl AppConfig {
+    /// Effective direction for a content type (`text`, `screenshot`, `file`)
+    pub fn direction_for(&self, content_type: &str) -> SyncDirection {
+        let overridden = match content_type {
+            "text" => self.direction_overrides.text,
+            "screenshot" => self.direction_overrides.screenshot,
+            "file" => self.direction_overrides.file,
+            _ => None,
+        };
+        overridden.unwrap_or(self.direction)
+    }
+
+    /// Reverse proxy settings of a server, if any
+    pub fn access_for(&self, server_url: &str) -> Option<&ServerAccess> {
+        self.server_access.iter().find(|access| {
+            access.server_url.trim_end_matches('/') == server_url.trim_end_matches('/')
+        })
+    }
+
+    /// Name of this device shown to other devices
+    pub fn device_name(&self) -> String {
+        match self.device_name.trim() {
+            "" => crate::device::host_name(),
+            name => name.to_string(),
+        }
+    }
+
+    /// Fill in header values and passwords from the secrets store
+    fn restore_access_secrets(&mut self) -> Result<(), AppError> {
+        let Some(stored) = secrets::get(SERVER_ACCESS_SECRET)? else {
+            return Ok(());
+        };
+        let mut stored: HashMap<String, AccessSecrets> =
+            serde_json::from_str(&stored).map_err(|e| AppError::Config(e.to_string()))?;
+
+        for access in &mut self.server_access {
+            let Some(secrets) = stored.remove(&access.server_url) else {
+                continue;
+            };
+            for (header, value) in access.headers.iter_mut().zip(secrets.header_values) {
+                header.value = value;
+            }
+            access.password = secrets.password;
+        }
+        Ok(())
+    }
+
+    /// Move header values and passwords to the secrets store
+    fn save_access_secrets(&self) -> Result<(), AppError> {
+        let stored: HashMap<&str, AccessSecrets> = self
+            .server_access
+            .iter()
+            .map(|access| {
+                let secrets = AccessSecrets {
+                    header_values: access.headers.iter().map(|h| h.value.clone()).collect(),
+                    password: access.password.clone(),
+                };
+                (access.server_url.as_str(), secrets)
+            })
+            .collect();
+
+        if stored.is_empty() {
+            return secrets::delete(SERVER_ACCESS_SECRET);
+        }
+        let content =
+            serde_json::to_string(&stored).map_err(|e| AppError::Config(e.to_string()))?;
+        secrets::set(SERVER_ACCESS_SECRET, &content)
+    }
+
     /// Load configuration from file
-    pub fn load() -> Result<Self, String> {
+    pub fn load() -> Result<Self, AppError> {
         let path = Self::config_path();
 
-        if path.exists() {
-            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-            serde_json::from_str(&content).map_err(|e| e.to_string())
+        let mut config: Self = if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|e| AppError::Config(e.to_string()))?;
+            serde_json::from_str(&content).map_err(|e| AppError::Config(e.to_string()))?
         } else {
-            Ok(Self::default())
+            Self::default()
+        };
+
+        // Older config files may still hold the passphrase; it moves to the
+        // secrets store on the next save
+        if config.encryption.passphrase.is_empty() {
+            match secrets::get(PASSPHRASE_SECRET) {
+                Ok(passphrase) => config.encryption.passphrase = passphrase.unwrap_or_default(),
+                Err(e) => log::error!("Failed to read encryption passphrase: {}", e),
+            }
+        }
+
+        if let Err(e) = config.restore_access_secrets() {
+            log::error!("Failed to read server access secrets: {}", e);
         }
+
+        Ok(config)
     }
 
     /// Save configuration to file
-    pub fn save(&self) -> Result<(), String> {
+    pub fn save(&self) -> Result<(), AppError> {
         let path = Self::config_path();
 
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            fs::create_dir_all(parent).map_err(|e| AppError::Config(e.to_string()))?;
+        }
+
+        if self.encryption.passphrase.is_empty() {
+            secrets::delete(PASSPHRASE_SECRET)?;
+        } else {
+            secrets::set(PASSPHRASE_SECRET, &self.encryption.passphrase)?;
+        }
+
+        self.save_access_secrets()?;
+
+        let mut stored = self.clone();
+        stored.encryption.passphrase.clear();
+        for access in &mut stored.server_access {
+            access.password.clear();
+            for header in &mut access.headers {
+                header.value.clear();
+            }
         }
 
-        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(path, content).map_err(|e| e.to_string())
+        let content =
+            serde_json::to_string_pretty(&stored).map_err(|e| AppError::Config(e.to_string()))?;
+        fs::write(path, content).map_err(|e| AppError::Config(e.to_string()))
     }
 
     /// Get configuration file path
@@ -100,12 +634,51 @@ impl AppConfig {
 
 /// Tauri command: Get current configuration
 #[tauri::command]
-pub fn get_config() -> Result<AppConfig, String> {
+pub fn get_config() -> Result<AppConfig, AppError> {
     AppConfig::load()
 }
 
 /// Tauri command: Save configuration
 #[tauri::command]
-pub fn save_config(config: AppConfig) -> Result<(), String> {
-    config.save()
+pub async fn save_config(app: AppHandle, config: AppConfig) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+
+    // Refuse network settings the client cannot be built with
+    let (client_changed, server_changed) = {
+        let current = state.config.lock().await;
+        let server_changed = current.server_url != config.server_url;
+        (
+            server_changed
+                || current.network != config.network
+                || current.server_access != config.server_access
+                || current.device_name != config.device_name,
+            server_changed,
+        )
+    };
+    if client_changed {
+        state.sync_state.rebuild_client(&config)?;
+    }
+
+    config.save()?;
+
+    if server_changed {
+        state.sync_state.set_server_url(&config.server_url);
+    }
+
+    crate::tray::set_direction_checked(&app, config.direction);
+    crate::tray::set_recent_repush_checked(&app, config.recent.repush);
+    crate::shortcuts::register_shortcuts(&app, &config.shortcuts);
+
+    *state.config.lock().await = config;
+    crate::tray::refresh_status(&app);
+
+    // Learn the account and limits of the new server
+    if server_changed {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            crate::auth::refresh_account(&app).await;
+            crate::limits::refresh_limits(&app).await;
+        });
+    }
+    Ok(())
 }
@@ -0,0 +1,322 @@
+//! Sync conflicts
+//!
+//! A conflict occurs when the local clipboard changed since the last sync and
+//! another device pushed a new item in the meantime. Depending on the
+//! configured [`ConflictPolicy`](crate::config::ConflictPolicy) it is resolved
+//! automatically, or kept pending here until the user picks a side. A
+//! conflict left unanswered for too long falls back to the default policy.
+
+use crate::clipboard::{read_clipboard, write_clipboard, ClipboardContent};
+use crate::config::ConflictPolicy;
+use crate::error::AppError;
+use crate::notifications::NotificationKind;
+use crate::sync::{SyncActivity, SyncEvent};
+use crate::transfer::TransferDirection;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Characters of remote text shown when asking the user
+const PREVIEW_LEN: usize = 100;
+
+/// How long the user has to pick a side before the default policy decides
+const ASK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Remote item waiting for the user to resolve a conflict
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingConflict {
+    /// Server index of the remote item
+    pub index: u64,
+    pub content_type: String,
+    /// Name of the device that pushed the remote item, if known
+    pub device: Option<String>,
+    /// Unix time in milliseconds the remote item was pushed, if known
+    pub remote_created_at: Option<u64>,
+    /// Unix time in milliseconds of the last local change
+    pub local_changed_at: u64,
+    /// Unix time in milliseconds the user was asked
+    pub asked_at: u64,
+    /// Beginning of the remote text, for text items
+    pub preview: Option<String>,
+    #[serde(skip)]
+    pub(crate) content: ClipboardContent,
+}
+
+impl PendingConflict {
+    pub(crate) fn new(
+        index: u64,
+        content_type: String,
+        device: Option<String>,
+        remote_created_at: Option<u64>,
+        content: ClipboardContent,
+    ) -> Self {
+        let preview = match &content {
+            ClipboardContent::Text(text) => Some(text.chars().take(PREVIEW_LEN).collect()),
+            ClipboardContent::Image(_) => None,
+        };
+
+        Self {
+            index,
+            content_type,
+            device,
+            remote_created_at,
+            local_changed_at: crate::clipboard::last_local_change_at(),
+            asked_at: now_millis(),
+            preview,
+            content,
+        }
+    }
+
+    /// Whether the user took too long to answer at the given time
+    fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.asked_at) >= ASK_TIMEOUT.as_millis() as u64
+    }
+}
+
+/// Side chosen by the user
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictChoice {
+    Local,
+    Remote,
+}
+
+/// Side a policy keeps, or `None` when the user has to choose
+pub(crate) fn decide(
+    policy: ConflictPolicy,
+    can_send: bool,
+    remote_created_at: Option<u64>,
+    local_changed_at: u64,
+) -> Option<ConflictChoice> {
+    let remote_wins = match policy {
+        // The local side can never be pushed
+        _ if !can_send => true,
+        ConflictPolicy::PreferRemote => true,
+        ConflictPolicy::PreferLocal => false,
+        // Without a timestamp the remote item cannot be older
+        ConflictPolicy::Newest => remote_created_at.is_none_or(|at| at >= local_changed_at),
+        ConflictPolicy::Ask => return None,
+    };
+
+    Some(if remote_wins {
+        ConflictChoice::Remote
+    } else {
+        ConflictChoice::Local
+    })
+}
+
+/// Keep a conflict for the user and tell them about it
+pub(crate) fn ask(app: &AppHandle, conflict: PendingConflict) {
+    let state = app.state::<crate::AppState>();
+    let content_type = conflict.content_type.clone();
+    let sender = conflict
+        .device
+        .clone()
+        .unwrap_or_else(|| "其他设备 Another device".into());
+
+    if let Ok(mut pending) = state.sync_state.conflict.lock() {
+        *pending = Some(conflict);
+    }
+
+    let _ = app.emit(
+        "sync-event",
+        SyncEvent::Conflict {
+            content_type: content_type.clone(),
+        },
+    );
+
+    crate::notifications::notify(
+        app,
+        NotificationKind::Conflict,
+        "GCopy: 同步冲突 sync conflict".into(),
+        format!(
+            "{sender}: {what} 冲突，请打开 GCopy 选择保留哪一个 \
+             Pushed while your clipboard changed. Open GCopy to choose which to keep.",
+            what = crate::notifications::type_label(&content_type),
+        ),
+    );
+
+    log::info!("Sync conflict on {}, waiting for the user", content_type);
+}
+
+/// Resolve a conflict left unanswered for too long with the default
+/// policy; returns whether a conflict is still waiting for the user
+pub(crate) async fn expire(app: &AppHandle) -> Result<bool, AppError> {
+    let choice = {
+        let state = app.state::<crate::AppState>();
+        let pending = state
+            .sync_state
+            .conflict
+            .lock()
+            .map_err(|e| AppError::Clipboard(e.to_string()))?;
+        match pending.as_ref() {
+            None => return Ok(false),
+            Some(conflict) if !conflict.is_expired(now_millis()) => return Ok(true),
+            Some(conflict) => decide(
+                ConflictPolicy::default(),
+                true,
+                conflict.remote_created_at,
+                conflict.local_changed_at,
+            ),
+        }
+    };
+
+    let choice = choice.unwrap_or(ConflictChoice::Remote);
+    log::info!("Sync conflict unanswered, keeping {:?} side", choice);
+    resolve_conflict(app.clone(), choice).await?;
+    Ok(false)
+}
+
+/// Tauri command: Get the conflict waiting for the user, if any
+#[tauri::command]
+pub fn get_pending_conflict(app: AppHandle) -> Option<PendingConflict> {
+    let state = app.state::<crate::AppState>();
+    let pending = state.sync_state.conflict.lock().ok()?;
+    pending.clone()
+}
+
+/// Tauri command: Resolve the pending conflict by keeping one side
+#[tauri::command]
+pub async fn resolve_conflict(app: AppHandle, choice: ConflictChoice) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+    let conflict = state
+        .sync_state
+        .conflict
+        .lock()
+        .map_err(|e| AppError::Clipboard(e.to_string()))?
+        .take();
+    let Some(conflict) = conflict else {
+        return Ok(());
+    };
+
+    match choice {
+        ConflictChoice::Remote => {
+            let size = match &conflict.content {
+                ClipboardContent::Text(text) => text.len(),
+                ClipboardContent::Image(data) => data.len(),
+            } as u64;
+            write_clipboard(conflict.content.clone())?;
+            crate::recent::record(&app, conflict.content, conflict.device.clone());
+            state.sync_state.record_pulled(conflict.index);
+            state.sync_state.record_activity(
+                TransferDirection::Download,
+                SyncActivity::now(&conflict.content_type, conflict.device.clone()),
+            );
+            crate::tray::refresh_status(&app);
+            let _ = app.emit(
+                "sync-event",
+                SyncEvent::Pulled {
+                    content_type: conflict.content_type,
+                    device: conflict.device,
+                    file_name: None,
+                    size,
+                    index: conflict.index,
+                },
+            );
+        }
+        ConflictChoice::Local => {
+            let content = read_clipboard()?;
+            let pushed = crate::sync::push_to_server(&app, content).await;
+            crate::sync::track(&app, pushed).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Current Unix time in milliseconds
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_pick_a_side() {
+        use ConflictChoice::{Local, Remote};
+
+        assert_eq!(
+            decide(ConflictPolicy::PreferLocal, true, None, 0),
+            Some(Local)
+        );
+        assert_eq!(
+            decide(ConflictPolicy::PreferRemote, true, None, 0),
+            Some(Remote)
+        );
+        assert_eq!(decide(ConflictPolicy::Ask, true, Some(1), 2), None);
+
+        // Newest compares the push time with the last local change
+        assert_eq!(
+            decide(ConflictPolicy::Newest, true, Some(2), 1),
+            Some(Remote)
+        );
+        assert_eq!(
+            decide(ConflictPolicy::Newest, true, Some(1), 2),
+            Some(Local)
+        );
+        assert_eq!(
+            decide(ConflictPolicy::Newest, true, Some(2), 2),
+            Some(Remote)
+        );
+        assert_eq!(decide(ConflictPolicy::Newest, true, None, 2), Some(Remote));
+    }
+
+    #[test]
+    fn remote_wins_when_the_local_side_cannot_be_sent() {
+        for policy in [
+            ConflictPolicy::PreferLocal,
+            ConflictPolicy::Newest,
+            ConflictPolicy::Ask,
+        ] {
+            assert_eq!(
+                decide(policy, false, Some(1), 2),
+                Some(ConflictChoice::Remote)
+            );
+        }
+    }
+
+    #[test]
+    fn text_conflicts_carry_a_preview() {
+        let text = "x".repeat(PREVIEW_LEN + 50);
+        let conflict = PendingConflict::new(
+            7,
+            "text".into(),
+            Some("laptop".into()),
+            Some(1),
+            ClipboardContent::Text(text),
+        );
+        assert_eq!(conflict.index, 7);
+        assert_eq!(conflict.device.as_deref(), Some("laptop"));
+        assert_eq!(conflict.preview.unwrap().chars().count(), PREVIEW_LEN);
+
+        let image = PendingConflict::new(
+            8,
+            "screenshot".into(),
+            None,
+            None,
+            ClipboardContent::Image(vec![1, 2, 3]),
+        );
+        assert!(image.preview.is_none());
+    }
+
+    #[test]
+    fn unanswered_conflicts_expire() {
+        let conflict = PendingConflict::new(
+            1,
+            "text".into(),
+            None,
+            None,
+            ClipboardContent::Text("remote".into()),
+        );
+        let timeout = ASK_TIMEOUT.as_millis() as u64;
+        assert!(!conflict.is_expired(conflict.asked_at));
+        assert!(!conflict.is_expired(conflict.asked_at + timeout - 1));
+        assert!(conflict.is_expired(conflict.asked_at + timeout));
+    }
+}
@@ -0,0 +1,281 @@
+//! Server connection state
+//!
+//! Tracks whether the server is reachable and how long to wait before the
+//! next attempt. Consecutive failures move the state from online to degraded
+//! to offline, with retries spaced by exponential backoff plus jitter.
+
+use crate::config::ReconnectConfig;
+use crate::error::AppError;
+use rand::Rng;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Connection state as seen by the sync engine
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionState {
+    /// Last request succeeded
+    Online,
+    /// Some requests failed, still retrying at short intervals
+    Degraded,
+    /// Server unreachable, retrying with backoff
+    Offline,
+    /// Server rejected the session; login required
+    Unauthorized,
+    /// Server certificate no longer matches the pinned one, retrying with backoff
+    CertificateMismatch,
+}
+
+struct Inner {
+    state: ConnectionState,
+    consecutive_failures: u32,
+    next_attempt: Option<Instant>,
+    last_error: Option<AppError>,
+}
+
+/// Connection state machine
+pub struct Connection {
+    inner: Mutex<Inner>,
+}
+
+impl Default for Connection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Connection {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                state: ConnectionState::Online,
+                consecutive_failures: 0,
+                next_attempt: None,
+                last_error: None,
+            }),
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> ConnectionState {
+        self.inner
+            .lock()
+            .map(|inner| inner.state)
+            .unwrap_or(ConnectionState::Online)
+    }
+
+    /// Number of failed attempts since the last success
+    pub fn consecutive_failures(&self) -> u32 {
+        self.inner
+            .lock()
+            .map(|inner| inner.consecutive_failures)
+            .unwrap_or_default()
+    }
+
+    /// Error of the last failed attempt, cleared on success
+    pub fn last_error(&self) -> Option<AppError> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.last_error.clone())
+    }
+
+    /// Time left until the next attempt is allowed
+    pub fn retry_in(&self) -> Option<Duration> {
+        let inner = self.inner.lock().ok()?;
+        inner
+            .next_attempt
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether an automatic attempt is allowed now
+    pub fn should_attempt(&self) -> bool {
+        self.retry_in().is_none_or(|left| left.is_zero())
+    }
+
+    /// Allow the next attempt immediately, e.g. after a manual sync or login
+    pub fn retry_now(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.next_attempt = None;
+        }
+    }
+
+    /// Record a successful request; returns the new state if it changed
+    pub fn record_success(&self) -> Option<ConnectionState> {
+        let mut inner = self.inner.lock().ok()?;
+        inner.consecutive_failures = 0;
+        inner.next_attempt = None;
+        inner.last_error = None;
+        Self::transition(&mut inner, ConnectionState::Online)
+    }
+
+    /// Record a failed request; returns the new state if it changed.
+    ///
+    /// Errors unrelated to the connection, such as clipboard or encryption
+    /// failures, leave the state untouched.
+    pub fn record_failure(
+        &self,
+        error: &AppError,
+        policy: &ReconnectConfig,
+    ) -> Option<ConnectionState> {
+        let next = match error {
+            AppError::Unauthorized => ConnectionState::Unauthorized,
+            AppError::CertificateMismatch { .. } => ConnectionState::CertificateMismatch,
+            AppError::Network(_) => ConnectionState::Offline,
+            AppError::Server { status, .. } if *status >= 500 => ConnectionState::Offline,
+            _ => return None,
+        };
+
+        let mut inner = self.inner.lock().ok()?;
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        inner.last_error = Some(error.clone());
+        inner.next_attempt = Some(Instant::now() + policy.delay(inner.consecutive_failures));
+
+        let next = if next == ConnectionState::Offline
+            && inner.consecutive_failures < policy.offline_after_failures
+        {
+            ConnectionState::Degraded
+        } else {
+            next
+        };
+        Self::transition(&mut inner, next)
+    }
+
+    fn transition(inner: &mut Inner, next: ConnectionState) -> Option<ConnectionState> {
+        if inner.state == next {
+            return None;
+        }
+        log::info!("Connection state: {:?} -> {:?}", inner.state, next);
+        inner.state = next;
+        Some(next)
+    }
+}
+
+impl ReconnectConfig {
+    /// Backoff delay before the attempt following `failures` failures
+    pub fn delay(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        let base = self
+            .initial_delay_secs
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay_secs) as f64;
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+
+        Duration::from_secs_f64(base * factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(jitter: f64) -> ReconnectConfig {
+        ReconnectConfig {
+            initial_delay_secs: 2,
+            max_delay_secs: 60,
+            jitter,
+            offline_after_failures: 3,
+        }
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let policy = policy(0.0);
+        let delays: Vec<u64> = (1..=7).map(|n| policy.delay(n).as_secs()).collect();
+        assert_eq!(delays, [2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(policy.delay(0).as_secs(), 2);
+        assert_eq!(policy.delay(u32::MAX).as_secs(), 60);
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let jittered = policy(0.2);
+        for failures in 1..=10 {
+            let base = policy(0.0).delay(failures).as_secs_f64();
+            for _ in 0..50 {
+                let delay = jittered.delay(failures).as_secs_f64();
+                assert!(delay >= base * 0.8 - 1e-9 && delay <= base * 1.2 + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_jitter_is_clamped() {
+        let policy = policy(5.0);
+        for _ in 0..50 {
+            assert!(policy.delay(1).as_secs_f64() <= 4.0);
+        }
+    }
+
+    #[test]
+    fn failures_degrade_then_go_offline() {
+        let policy = policy(0.0);
+        let connection = Connection::new();
+        let error = AppError::Network("refused".into());
+
+        assert_eq!(
+            connection.record_failure(&error, &policy),
+            Some(ConnectionState::Degraded)
+        );
+        assert_eq!(connection.record_failure(&error, &policy), None);
+        assert_eq!(
+            connection.record_failure(&error, &policy),
+            Some(ConnectionState::Offline)
+        );
+        assert_eq!(connection.consecutive_failures(), 3);
+        assert!(!connection.should_attempt());
+
+        connection.retry_now();
+        assert!(connection.should_attempt());
+
+        assert_eq!(connection.record_success(), Some(ConnectionState::Online));
+        assert_eq!(connection.consecutive_failures(), 0);
+        assert!(connection.last_error().is_none());
+    }
+
+    #[test]
+    fn unrelated_errors_leave_the_state_alone() {
+        let connection = Connection::new();
+        let error = AppError::Encryption("wrong key".into());
+        assert_eq!(connection.record_failure(&error, &policy(0.0)), None);
+        assert_eq!(connection.state(), ConnectionState::Online);
+        assert!(connection.should_attempt());
+    }
+
+    #[test]
+    fn unauthorized_is_reported_right_away() {
+        let connection = Connection::new();
+        assert_eq!(
+            connection.record_failure(&AppError::Unauthorized, &policy(0.0)),
+            Some(ConnectionState::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn certificate_mismatch_is_reported_with_backoff() {
+        let connection = Connection::new();
+        let error = AppError::CertificateMismatch {
+            host: "example.com".into(),
+            expected: "aa".into(),
+            actual: "bb".into(),
+        };
+        assert_eq!(
+            connection.record_failure(&error, &policy(0.0)),
+            Some(ConnectionState::CertificateMismatch)
+        );
+        assert_eq!(connection.record_failure(&error, &policy(0.0)), None);
+        assert_eq!(connection.consecutive_failures(), 2);
+        assert!(!connection.should_attempt());
+        assert!(matches!(
+            connection.last_error(),
+            Some(AppError::CertificateMismatch { .. })
+        ));
+    }
+}
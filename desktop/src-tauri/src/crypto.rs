@@ -0,0 +1,564 @@
+//! End-to-end encryption of clipboard payloads
+//!
+//! Payloads are sealed with XChaCha20-Poly1305 before they leave the device.
+//! The key is either derived from a shared passphrase with Argon2id or a
+//! random key shared by device pairing. The server only ever sees ciphertext.
+//!
+//! Wire formats of an encrypted payload:
+//!
+//! ```text
+//! passphrase: "GCE1" | salt (16)   | nonce (24) | ciphertext + tag
+//! paired key: "GCK1" | key id (8)  | nonce (24) | ciphertext + tag
+//! ```
+
+use crate::config::EncryptionConfig;
+use crate::error::AppError;
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Marker prepended to payloads sealed with a passphrase-derived key
+pub const MAGIC: &[u8; 4] = b"GCE1";
+
+/// Marker prepended to payloads sealed with a paired key
+pub const MAGIC_PAIRED: &[u8; 4] = b"GCK1";
+
+/// Content-Type sent along with encrypted payloads
+pub const ENCRYPTED_MIME_TYPE: &str = "application/x-gcopy-encrypted";
+
+/// Prefix marking an encrypted `X-FileName` header value
+const FILE_NAME_PREFIX: &str = "gce1:";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+pub const KEY_LEN: usize = 32;
+const KEY_ID_LEN: usize = 8;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+const PAIRED_HEADER_LEN: usize = MAGIC_PAIRED.len() + KEY_ID_LEN + NONCE_LEN;
+
+/// Keys derived from the passphrase, by salt. Payloads pulled from other
+/// devices carry their salts, so each key is derived only once.
+struct KeyCache {
+    passphrase: String,
+    /// Salt of outgoing payloads
+    local_salt: Option<[u8; SALT_LEN]>,
+    keys: HashMap<[u8; SALT_LEN], [u8; KEY_LEN]>,
+}
+
+/// Salts remembered before the cache starts over
+const MAX_CACHED_KEYS: usize = 16;
+
+static KEY_CACHE: Mutex<Option<KeyCache>> = Mutex::new(None);
+
+/// Random key shared between devices by pairing
+#[derive(Clone)]
+pub struct PairedKey {
+    id: [u8; KEY_ID_LEN],
+    key: [u8; KEY_LEN],
+}
+
+impl PairedKey {
+    /// Generate a fresh random key
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        Self::from_bytes(key)
+    }
+
+    /// Wrap existing key material
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> Self {
+        let digest = Sha256::digest(key);
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&digest[..KEY_ID_LEN]);
+        Self { id, key }
+    }
+
+    /// Raw key material
+    pub fn bytes(&self) -> &[u8; KEY_LEN] {
+        &self.key
+    }
+
+    /// Short identifier of the key, safe to display
+    pub fn id(&self) -> String {
+        hex::encode(self.id)
+    }
+}
+
+/// Check whether a payload carries one of the encryption markers
+pub fn is_encrypted(data: &[u8]) -> bool {
+    (data.len() >= HEADER_LEN && data.starts_with(MAGIC))
+        || (data.len() >= PAIRED_HEADER_LEN && data.starts_with(MAGIC_PAIRED))
+}
+
+/// Identifier of the paired key a payload was sealed with, if any
+pub fn payload_key_id(data: &[u8]) -> Option<String> {
+    if data.len() >= PAIRED_HEADER_LEN && data.starts_with(MAGIC_PAIRED) {
+        Some(hex::encode(
+            &data[MAGIC_PAIRED.len()..MAGIC_PAIRED.len() + KEY_ID_LEN],
+        ))
+    } else {
+        None
+    }
+}
+
+/// Derive a key for the passphrase, reusing a cached one when possible.
+///
+/// Passing `None` as salt uses the local salt for this passphrase, generating
+/// one on first use. Argon2 is slow; call this off the async executor.
+fn derive_key(
+    passphrase: &str,
+    salt: Option<[u8; SALT_LEN]>,
+) -> Result<([u8; SALT_LEN], [u8; KEY_LEN]), AppError> {
+    let cached = {
+        let cache = KEY_CACHE
+            .lock()
+            .map_err(|e| AppError::Encryption(e.to_string()))?;
+        cache
+            .as_ref()
+            .filter(|cache| cache.passphrase == passphrase)
+            .and_then(|cache| {
+                let salt = salt.or(cache.local_salt)?;
+                cache.keys.get(&salt).map(|key| (salt, *key))
+            })
+    };
+    if let Some(cached) = cached {
+        return Ok(cached);
+    }
+
+    let local = salt.is_none();
+    let salt = salt.unwrap_or_else(|| {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    });
+
+    // Derive without holding the lock so other keys stay available
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| AppError::Encryption(format!("Key derivation failed: {}", e)))?;
+
+    let mut cache = KEY_CACHE
+        .lock()
+        .map_err(|e| AppError::Encryption(e.to_string()))?;
+    if cache
+        .as_ref()
+        .is_some_and(|cache| cache.passphrase != passphrase)
+    {
+        *cache = None;
+    }
+    let cache = cache.get_or_insert_with(|| KeyCache {
+        passphrase: passphrase.to_string(),
+        local_salt: None,
+        keys: HashMap::new(),
+    });
+    if local {
+        cache.local_salt = Some(salt);
+    }
+    if cache.keys.len() >= MAX_CACHED_KEYS {
+        let local_salt = cache.local_salt;
+        cache.keys.retain(|salt, _| Some(*salt) == local_salt);
+    }
+    cache.keys.insert(salt, key);
+
+    Ok((salt, key))
+}
+
+/// Seal data under a key, prefixing the given header
+fn seal(key: &[u8; KEY_LEN], header: &[u8], data: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), data)
+        .map_err(|_| AppError::Encryption("Encryption failed".into()))?;
+
+    let mut out = Vec::with_capacity(header.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(header);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Open data sealed by [`seal`], given the nonce and ciphertext
+fn open(key: &[u8; KEY_LEN], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, AppError> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            AppError::Encryption("Decryption failed: wrong key or corrupted payload".into())
+        })
+}
+
+/// Encrypt a payload with the shared passphrase
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+    let (salt, key) = derive_key(passphrase, None)?;
+
+    let mut header = Vec::with_capacity(MAGIC.len() + SALT_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&salt);
+    seal(&key, &header, data)
+}
+
+/// Decrypt a payload produced by [`encrypt`]
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+    if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+        return Err(AppError::Encryption(
+            "Payload is not encrypted with a passphrase".into(),
+        ));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&data[MAGIC.len()..MAGIC.len() + SALT_LEN]);
+    let nonce = &data[MAGIC.len() + SALT_LEN..HEADER_LEN];
+
+    let (_, key) = derive_key(passphrase, Some(salt))?;
+    open(&key, nonce, &data[HEADER_LEN..])
+}
+
+/// Encrypt a payload with a paired key
+pub fn encrypt_with_key(data: &[u8], key: &PairedKey) -> Result<Vec<u8>, AppError> {
+    let mut header = Vec::with_capacity(MAGIC_PAIRED.len() + KEY_ID_LEN);
+    header.extend_from_slice(MAGIC_PAIRED);
+    header.extend_from_slice(&key.id);
+    seal(&key.key, &header, data)
+}
+
+/// Decrypt a payload produced by [`encrypt_with_key`]
+pub fn decrypt_with_key(data: &[u8], key: &PairedKey) -> Result<Vec<u8>, AppError> {
+    let Some(key_id) = payload_key_id(data) else {
+        return Err(AppError::Encryption(
+            "Payload is not encrypted with a paired key".into(),
+        ));
+    };
+    if key_id != key.id() {
+        return Err(AppError::Encryption(format!(
+            "Payload was encrypted with key {}, but this device uses key {}",
+            key_id,
+            key.id()
+        )));
+    }
+
+    let nonce = &data[MAGIC_PAIRED.len() + KEY_ID_LEN..PAIRED_HEADER_LEN];
+    open(&key.key, nonce, &data[PAIRED_HEADER_LEN..])
+}
+
+/// Keys available on this device for sealing and opening payloads
+#[derive(Clone, Default)]
+pub struct Cipher {
+    passphrase: Option<String>,
+    paired: Option<PairedKey>,
+    retired_key_ids: Vec<String>,
+    require_encrypted: bool,
+}
+
+impl Cipher {
+    /// Build a cipher from the encryption settings and the paired key, if any.
+    ///
+    /// A paired key takes precedence over the passphrase for outgoing payloads.
+    pub fn new(
+        encryption: &EncryptionConfig,
+        paired: Option<PairedKey>,
+        retired_key_ids: Vec<String>,
+    ) -> Self {
+        Self {
+            passphrase: encryption.active_passphrase().map(str::to_string),
+            require_encrypted: encryption.require_encrypted
+                && (encryption.active_passphrase().is_some() || paired.is_some()),
+            paired,
+            retired_key_ids,
+        }
+    }
+
+    /// Whether outgoing payloads will be encrypted
+    pub fn is_active(&self) -> bool {
+        self.paired.is_some() || self.passphrase.is_some()
+    }
+
+    /// Encrypt an outgoing payload, or pass it through when inactive
+    pub async fn seal(&self, data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        let cipher = self.clone();
+        blocking(move || cipher.seal_blocking(data)).await
+    }
+
+    fn seal_blocking(&self, data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        match (&self.paired, &self.passphrase) {
+            (Some(key), _) => encrypt_with_key(&data, key),
+            (None, Some(passphrase)) => encrypt(&data, passphrase),
+            (None, None) => Ok(data),
+        }
+    }
+
+    /// Remove encryption from a pulled payload.
+    ///
+    /// Payloads that cannot be opened are reported with a reason instead of
+    /// being returned as garbage.
+    pub async fn open(&self, data: Vec<u8>, mime_type: &str) -> Result<Vec<u8>, AppError> {
+        let cipher = self.clone();
+        let mime_type = mime_type.to_string();
+        blocking(move || cipher.open_blocking(data, &mime_type)).await
+    }
+
+    fn open_blocking(&self, data: Vec<u8>, mime_type: &str) -> Result<Vec<u8>, AppError> {
+        if let Some(key_id) = payload_key_id(&data) {
+            let Some(key) = &self.paired else {
+                return Err(AppError::Encryption(format!(
+                    "Received content encrypted with paired key {}, but this device is not paired",
+                    key_id
+                )));
+            };
+            if key_id != key.id() {
+                return Err(AppError::Encryption(
+                    if self.retired_key_ids.contains(&key_id) {
+                        format!(
+                        "Received content encrypted with retired key {}; re-pair the sending device",
+                        key_id
+                    )
+                    } else {
+                        format!(
+                        "Received content encrypted with unknown key {}; import the current pairing code",
+                        key_id
+                    )
+                    },
+                ));
+            }
+            return decrypt_with_key(&data, key);
+        }
+
+        if is_encrypted(&data) || mime_type == ENCRYPTED_MIME_TYPE {
+            let passphrase = self.passphrase.as_deref().ok_or_else(|| {
+                AppError::Encryption(
+                    "Received encrypted content but no passphrase is configured".into(),
+                )
+            })?;
+            return decrypt(&data, passphrase);
+        }
+
+        if self.is_active() {
+            if self.require_encrypted {
+                return Err(AppError::Encryption(
+                    "Received unencrypted content from a client without encryption".into(),
+                ));
+            }
+            log::warn!("Received unencrypted content from a client without encryption");
+        }
+
+        Ok(data)
+    }
+
+    /// Decrypt an `X-FileName` header value, passing plain names through
+    pub async fn open_file_name(&self, value: &str) -> Result<String, AppError> {
+        let Some(encoded) = value.strip_prefix(FILE_NAME_PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let sealed = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| AppError::Encryption(e.to_string()))?;
+        if !is_encrypted(&sealed) {
+            return Err(AppError::Encryption(
+                "Encrypted file name is malformed".into(),
+            ));
+        }
+        let name = self.open(sealed, ENCRYPTED_MIME_TYPE).await?;
+        String::from_utf8(name).map_err(|e| AppError::Encryption(e.to_string()))
+    }
+}
+
+/// Run key derivation and encryption on a blocking thread
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Encryption(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase_cipher(passphrase: &str, require_encrypted: bool) -> Cipher {
+        let encryption = EncryptionConfig {
+            enabled: true,
+            passphrase: passphrase.into(),
+            require_encrypted,
+        };
+        Cipher::new(&encryption, None, Vec::new())
+    }
+
+    fn paired_cipher(key: &PairedKey, retired_key_ids: Vec<String>) -> Cipher {
+        Cipher::new(
+            &EncryptionConfig::default(),
+            Some(key.clone()),
+            retired_key_ids,
+        )
+    }
+
+    #[test]
+    fn passphrase_round_trip() {
+        let sealed = encrypt(b"hello", "correct horse").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(is_encrypted(&sealed));
+        assert_eq!(decrypt(&sealed, "correct horse").unwrap(), b"hello");
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let sealed = encrypt(b"hello", "correct horse").unwrap();
+        assert!(matches!(
+            decrypt(&sealed, "battery staple"),
+            Err(AppError::Encryption(_))
+        ));
+    }
+
+    #[test]
+    fn payloads_with_other_salts_open() {
+        // Salts of other devices interleaved with the local one
+        let key = derive_key("shared", Some([1; SALT_LEN])).unwrap().1;
+        let remote = seal(
+            &key,
+            &[MAGIC.as_slice(), &[1; SALT_LEN]].concat(),
+            b"remote",
+        )
+        .unwrap();
+        let local = encrypt(b"local", "shared").unwrap();
+
+        assert_eq!(decrypt(&remote, "shared").unwrap(), b"remote");
+        assert_eq!(decrypt(&local, "shared").unwrap(), b"local");
+        assert_eq!(decrypt(&remote, "shared").unwrap(), b"remote");
+    }
+
+    #[test]
+    fn paired_round_trip() {
+        let key = PairedKey::generate();
+        let sealed = encrypt_with_key(b"hello", &key).unwrap();
+        assert!(sealed.starts_with(MAGIC_PAIRED));
+        assert_eq!(payload_key_id(&sealed), Some(key.id()));
+        assert_eq!(decrypt_with_key(&sealed, &key).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn wrong_paired_key_is_rejected() {
+        let sealed = encrypt_with_key(b"hello", &PairedKey::generate()).unwrap();
+        assert!(decrypt_with_key(&sealed, &PairedKey::generate()).is_err());
+    }
+
+    #[test]
+    fn tampered_payload_is_rejected() {
+        let key = PairedKey::generate();
+        let mut sealed = encrypt_with_key(b"hello", &key).unwrap();
+        *sealed.last_mut().unwrap() ^= 1;
+        assert!(decrypt_with_key(&sealed, &key).is_err());
+    }
+
+    #[test]
+    fn key_id_is_stable() {
+        let key = PairedKey::generate();
+        assert_eq!(PairedKey::from_bytes(*key.bytes()).id(), key.id());
+        assert_eq!(key.id().len(), KEY_ID_LEN * 2);
+    }
+
+    #[test]
+    fn cipher_prefers_paired_key() {
+        let key = PairedKey::generate();
+        let encryption = EncryptionConfig {
+            enabled: true,
+            passphrase: "shared".into(),
+            require_encrypted: false,
+        };
+        let cipher = Cipher::new(&encryption, Some(key.clone()), Vec::new());
+
+        let sealed = cipher.seal_blocking(b"hello".to_vec()).unwrap();
+        assert_eq!(payload_key_id(&sealed), Some(key.id()));
+        assert_eq!(cipher.open_blocking(sealed, "").unwrap(), b"hello");
+    }
+
+    #[test]
+    fn cipher_reports_retired_and_unknown_keys() {
+        let old = PairedKey::generate();
+        let sealed = encrypt_with_key(b"hello", &old).unwrap();
+
+        let retired = paired_cipher(&PairedKey::generate(), vec![old.id()]);
+        let Err(AppError::Encryption(message)) = retired.open_blocking(sealed.clone(), "") else {
+            panic!("retired key accepted");
+        };
+        assert!(message.contains("retired"));
+
+        let unknown = paired_cipher(&PairedKey::generate(), Vec::new());
+        let Err(AppError::Encryption(message)) = unknown.open_blocking(sealed, "") else {
+            panic!("unknown key accepted");
+        };
+        assert!(message.contains("unknown"));
+    }
+
+    #[test]
+    fn cipher_handles_unencrypted_payloads() {
+        let inactive = Cipher::default();
+        assert!(!inactive.is_active());
+        assert_eq!(inactive.seal_blocking(b"plain".to_vec()).unwrap(), b"plain");
+        assert_eq!(
+            inactive.open_blocking(b"plain".to_vec(), "").unwrap(),
+            b"plain"
+        );
+
+        let lenient = passphrase_cipher("shared", false);
+        assert_eq!(
+            lenient.open_blocking(b"plain".to_vec(), "").unwrap(),
+            b"plain"
+        );
+
+        let strict = passphrase_cipher("shared", true);
+        assert!(strict.open_blocking(b"plain".to_vec(), "").is_err());
+    }
+
+    /// `X-FileName` value as sent by an encrypting client
+    fn sealed_file_name(cipher: &Cipher, name: &str) -> String {
+        let sealed = cipher.seal_blocking(name.as_bytes().to_vec()).unwrap();
+        format!("{}{}", FILE_NAME_PREFIX, URL_SAFE_NO_PAD.encode(sealed))
+    }
+
+    #[test]
+    fn file_name_round_trip() {
+        let cipher = paired_cipher(&PairedKey::generate(), Vec::new());
+        let name = urlencoding::encode("report 2024 (final).pdf").into_owned();
+        let sealed = sealed_file_name(&cipher, &name);
+        assert!(!sealed.contains("report"));
+
+        let opened = tauri::async_runtime::block_on(cipher.open_file_name(&sealed)).unwrap();
+        assert_eq!(opened, name);
+    }
+
+    #[test]
+    fn file_names_need_the_right_key() {
+        let sealed = sealed_file_name(&paired_cipher(&PairedKey::generate(), Vec::new()), "a.txt");
+        let other = paired_cipher(&PairedKey::generate(), Vec::new());
+        assert!(tauri::async_runtime::block_on(other.open_file_name(&sealed)).is_err());
+
+        let malformed = format!("{}not-base64!", FILE_NAME_PREFIX);
+        assert!(tauri::async_runtime::block_on(other.open_file_name(&malformed)).is_err());
+    }
+
+    #[test]
+    fn plain_file_names_pass_through() {
+        let cipher = paired_cipher(&PairedKey::generate(), Vec::new());
+        let opened = tauri::async_runtime::block_on(cipher.open_file_name("a.txt")).unwrap();
+        assert_eq!(opened, "a.txt");
+    }
+
+    #[test]
+    fn encrypted_payload_without_passphrase_is_rejected() {
+        let sealed = encrypt(b"hello", "shared").unwrap();
+        assert!(Cipher::default()
+            .open_blocking(sealed, ENCRYPTED_MIME_TYPE)
+            .is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Persisted sync cursor
+//!
+//! Remembers how far this device has synced with a server account, so a
+//! restart does not re-download the current server item and overwrite a
+//! newer local clipboard.
+
+use crate::error::AppError;
+use crate::fingerprint::Fingerprint;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Sync position with a server account
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncCursor {
+    /// Server the cursor belongs to
+    pub server_url: String,
+
+    /// Account the cursor belongs to, if known
+    pub account: Option<String>,
+
+    /// Last server index pulled or pushed
+    pub last_index: u64,
+
+    /// Fingerprint of the local clipboard at the last sync
+    #[serde(default)]
+    pub last_local_fingerprint: Option<Fingerprint>,
+
+    /// Last item this device pushed
+    #[serde(default)]
+    pub last_pushed: Option<SyncedItem>,
+
+    /// Last item this device pulled
+    #[serde(default)]
+    pub last_pulled: Option<SyncedItem>,
+}
+
+/// Server item with the fingerprint of its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedItem {
+    /// Server index of the item
+    pub index: u64,
+    /// Fingerprint of the content as read from the local clipboard
+    pub fingerprint: Fingerprint,
+}
+
+impl SyncCursor {
+    /// Load the cursor from file
+    pub fn load() -> Result<Self, AppError> {
+        Self::load_from(&Self::cursor_path())
+    }
+
+    fn load_from(path: &Path) -> Result<Self, AppError> {
+        if path.exists() {
+            let content = fs::read_to_string(path).map_err(|e| AppError::Config(e.to_string()))?;
+            serde_json::from_str(&content).map_err(|e| AppError::Config(e.to_string()))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Save the cursor to file
+    pub fn save(&self) -> Result<(), AppError> {
+        self.save_to(&Self::cursor_path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), AppError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::Config(e.to_string()))?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| AppError::Config(e.to_string()))?;
+        fs::write(path, content).map_err(|e| AppError::Config(e.to_string()))
+    }
+
+    /// Whether the cursor was recorded against the given server
+    pub fn matches_server(&self, server_url: &str) -> bool {
+        self.server_url.trim_end_matches('/') == server_url.trim_end_matches('/')
+    }
+
+    /// Get cursor file path
+    fn cursor_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("gcopy")
+            .join("cursor.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("gcopy").join("cursor.json");
+        let fingerprint = Fingerprint::of_payload("text", b"hello");
+
+        let cursor = SyncCursor {
+            server_url: "https://gcopy.example.com".into(),
+            account: Some("me@example.com".into()),
+            last_index: 42,
+            last_local_fingerprint: Some(fingerprint),
+            last_pushed: Some(SyncedItem {
+                index: 42,
+                fingerprint,
+            }),
+            last_pulled: None,
+        };
+        cursor.save_to(&path).unwrap();
+
+        let loaded = SyncCursor::load_from(&path).unwrap();
+        assert_eq!(loaded.server_url, cursor.server_url);
+        assert_eq!(loaded.account, cursor.account);
+        assert_eq!(loaded.last_index, 42);
+        assert_eq!(loaded.last_local_fingerprint, Some(fingerprint));
+        assert_eq!(loaded.last_pushed, cursor.last_pushed);
+        assert_eq!(loaded.last_pulled, None);
+    }
+
+    #[test]
+    fn missing_file_is_a_fresh_cursor() {
+        let dir = TempDir::new().unwrap();
+        let cursor = SyncCursor::load_from(&dir.path().join("cursor.json")).unwrap();
+        assert_eq!(cursor.last_index, 0);
+        assert!(!cursor.matches_server("https://gcopy.example.com"));
+    }
+
+    #[test]
+    fn corrupted_file_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cursor.json");
+        fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(
+            SyncCursor::load_from(&path),
+            Err(AppError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn cursor_is_only_used_for_its_server() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cursor.json");
+        SyncCursor {
+            server_url: "https://gcopy.example.com/".into(),
+            last_index: 7,
+            ..Default::default()
+        }
+        .save_to(&path)
+        .unwrap();
+
+        let cursor = SyncCursor::load_from(&path).unwrap();
+        assert!(cursor.matches_server("https://gcopy.example.com"));
+        assert!(cursor.matches_server("https://gcopy.example.com/"));
+        assert!(!cursor.matches_server("https://other.example.com"));
+        assert!(!cursor.matches_server("http://gcopy.example.com"));
+        assert!(!cursor.matches_server("https://gcopy.example.com:8443"));
+    }
+}
@@ -0,0 +1,55 @@
+//! Device identity
+//!
+//! Pushed items carry the name of the device they came from, so other
+//! devices can tell where an item was copied. The name is sent in the
+//! `X-ClientName` header; the User-Agent also names the operating system,
+//! which older servers derive the client name from.
+
+/// Header carrying the device name of a pushed item, percent-encoded
+pub const CLIENT_NAME_HEADER: &str = "X-ClientName";
+
+/// Host name of this machine, used when no device name is configured
+pub fn host_name() -> String {
+    let name = gethostname::gethostname().to_string_lossy().into_owned();
+    match name.trim() {
+        "" => "GCopy Desktop".into(),
+        name => name.trim_end_matches(".local").to_string(),
+    }
+}
+
+/// User-Agent sent with every request
+pub fn user_agent(device_name: &str) -> String {
+    // Only visible ASCII is allowed in header values
+    let device: String = device_name
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if !matches!(c, '(' | ')' | ';') => c,
+            _ => '_',
+        })
+        .collect();
+
+    format!(
+        "GCopy-Desktop/{} ({}; {})",
+        env!("CARGO_PKG_VERSION"),
+        os_token(),
+        device
+    )
+}
+
+/// Operating system as the server's User-Agent parser recognizes it
+fn os_token() -> &'static str {
+    match std::env::consts::OS {
+        "windows" => "Windows NT 10.0",
+        "macos" => "Macintosh; Mac OS X",
+        _ => "X11; Linux",
+    }
+}
+
+/// Sender device of a pulled item from its `X-ClientName` header
+pub fn client_name(header: Option<&str>) -> Option<String> {
+    let header = header?.trim();
+    let name = urlencoding::decode(header)
+        .map(|name| name.into_owned())
+        .unwrap_or_else(|_| header.to_string());
+    Some(name).filter(|name| !name.is_empty())
+}
@@ -0,0 +1,303 @@
+//! Application error type
+//!
+//! Every command returns [`AppError`] on failure. It is serialized to the
+//! frontend as `{ code, message, details }`, where `code` is stable and can
+//! be matched on.
+
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Errors surfaced by commands and sync events
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AppError {
+    /// The clipboard has no content in a supported format
+    #[error("Clipboard is empty or contains unsupported format")]
+    ClipboardEmpty,
+
+    /// Reading or writing the system clipboard failed
+    #[error("Clipboard error: {0}")]
+    Clipboard(String),
+
+    /// Loading or saving configuration failed
+    #[error("Config error: {0}")]
+    Config(String),
+
+    /// Reading a local file failed
+    #[error("File error: {0}")]
+    File(String),
+
+    /// Reading or writing the secrets store failed
+    #[error("Secrets store error: {0}")]
+    Storage(String),
+
+    /// The server could not be reached
+    #[error("Network error: {0}")]
+    Network(String),
+
+    /// The server rejected the session
+    #[error("Not logged in")]
+    Unauthorized,
+
+    /// Login or account lookup failed
+    #[error("{0}")]
+    Auth(String),
+
+    /// The server answered with an unexpected error status
+    #[error("Server error {status}: {message}")]
+    Server { status: u16, message: String },
+
+    /// The server answered with something the client does not understand
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// The payload exceeds the server's content length limit
+    #[error("{message}")]
+    TooLarge {
+        message: String,
+        size: Option<u64>,
+        limit: Option<u64>,
+    },
+
+    /// Encrypting or decrypting a payload failed
+    #[error("{0}")]
+    Encryption(String),
+
+    /// The sync direction does not allow the operation
+    #[error("{0}")]
+    Direction(String),
+
+    /// The server presented a certificate other than the pinned one
+    #[error("Certificate of {host} does not match the pinned certificate; reset the pin if the server certificate was replaced")]
+    CertificateMismatch {
+        host: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// The user cancelled the transfer
+    #[error("Transfer cancelled")]
+    Cancelled,
+
+    /// Another sync is already running
+    #[error("Sync already in progress")]
+    SyncInProgress,
+}
+
+impl AppError {
+    /// Stable code for the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ClipboardEmpty => "clipboard_empty",
+            Self::Clipboard(_) => "clipboard",
+            Self::Config(_) => "config",
+            Self::File(_) => "file",
+            Self::Storage(_) => "storage",
+            Self::Network(_) => "network",
+            Self::Unauthorized => "unauthorized",
+            Self::Auth(_) => "auth",
+            Self::Server { .. } => "server",
+            Self::Protocol(_) => "protocol",
+            Self::TooLarge { .. } => "too_large",
+            Self::Encryption(_) => "encryption",
+            Self::Direction(_) => "direction",
+            Self::CertificateMismatch { .. } => "certificate_mismatch",
+            Self::Cancelled => "cancelled",
+            Self::SyncInProgress => "sync_in_progress",
+        }
+    }
+
+    /// Whether the error means the server could not be used right now
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::Unauthorized | Self::CertificateMismatch { .. } => true,
+            Self::Server { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Structured details for the frontend, if any
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Server { status, .. } => Some(serde_json::json!({ "status": status })),
+            Self::TooLarge { size, limit, .. } => {
+                Some(serde_json::json!({ "size": size, "limit": limit }))
+            }
+            Self::CertificateMismatch {
+                host,
+                expected,
+                actual,
+            } => Some(serde_json::json!({
+                "host": host,
+                "expected": expected,
+                "actual": actual,
+            })),
+            _ => None,
+        }
+    }
+
+    /// Map an unsuccessful response to an error, reading the server's message
+    pub async fn from_response(resp: reqwest::Response) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            message: String,
+        }
+
+        let status = resp.status();
+        let message = resp
+            .json::<ErrorBody>()
+            .await
+            .map(|body| body.message)
+            .unwrap_or_else(|_| status.canonical_reason().unwrap_or_default().to_string());
+
+        match status.as_u16() {
+            401 => Self::Unauthorized,
+            413 => Self::TooLarge {
+                message,
+                size: None,
+                limit: None,
+            },
+            status => Self::Server { status, message },
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Self::Protocol(e.to_string())
+        } else {
+            Self::Network(e.to_string())
+        }
+    }
+}
+
+impl From<arboard::Error> for AppError {
+    fn from(e: arboard::Error) -> Self {
+        match e {
+            arboard::Error::ContentNotAvailable => Self::ClipboardEmpty,
+            e => Self::Clipboard(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mismatch() -> AppError {
+        AppError::CertificateMismatch {
+            host: "example.com".into(),
+            expected: "aa".into(),
+            actual: "bb".into(),
+        }
+    }
+
+    #[test]
+    fn every_variant_has_a_stable_code() {
+        let cases = [
+            (AppError::ClipboardEmpty, "clipboard_empty"),
+            (AppError::Clipboard("x".into()), "clipboard"),
+            (AppError::Config("x".into()), "config"),
+            (AppError::File("x".into()), "file"),
+            (AppError::Storage("x".into()), "storage"),
+            (AppError::Network("x".into()), "network"),
+            (AppError::Unauthorized, "unauthorized"),
+            (AppError::Auth("x".into()), "auth"),
+            (
+                AppError::Server {
+                    status: 500,
+                    message: "x".into(),
+                },
+                "server",
+            ),
+            (AppError::Protocol("x".into()), "protocol"),
+            (
+                AppError::TooLarge {
+                    message: "x".into(),
+                    size: None,
+                    limit: None,
+                },
+                "too_large",
+            ),
+            (AppError::Encryption("x".into()), "encryption"),
+            (AppError::Direction("x".into()), "direction"),
+            (mismatch(), "certificate_mismatch"),
+            (AppError::Cancelled, "cancelled"),
+            (AppError::SyncInProgress, "sync_in_progress"),
+        ];
+
+        for (error, code) in cases {
+            let value = serde_json::to_value(&error).unwrap();
+            assert_eq!(value["code"], code);
+            assert_eq!(value["message"], error.to_string());
+        }
+    }
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        assert_eq!(
+            serde_json::to_value(AppError::Unauthorized).unwrap(),
+            json!({ "code": "unauthorized", "message": "Not logged in", "details": null })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::Server {
+                status: 502,
+                message: "Bad Gateway".into(),
+            })
+            .unwrap(),
+            json!({
+                "code": "server",
+                "message": "Server error 502: Bad Gateway",
+                "details": { "status": 502 },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::TooLarge {
+                message: "Content length cannot exceed 10MiB".into(),
+                size: Some(20),
+                limit: Some(10),
+            })
+            .unwrap(),
+            json!({
+                "code": "too_large",
+                "message": "Content length cannot exceed 10MiB",
+                "details": { "size": 20, "limit": 10 },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(mismatch()).unwrap()["details"],
+            json!({ "host": "example.com", "expected": "aa", "actual": "bb" })
+        );
+    }
+
+    #[test]
+    fn classifies_connection_errors() {
+        let server = |status| AppError::Server {
+            status,
+            message: String::new(),
+        };
+
+        assert!(AppError::Network("refused".into()).is_connection_error());
+        assert!(AppError::Unauthorized.is_connection_error());
+        assert!(mismatch().is_connection_error());
+        assert!(server(500).is_connection_error());
+        assert!(server(503).is_connection_error());
+
+        assert!(!server(400).is_connection_error());
+        assert!(!server(404).is_connection_error());
+        assert!(!AppError::Protocol("bad".into()).is_connection_error());
+        assert!(!AppError::Encryption("wrong key".into()).is_connection_error());
+        assert!(!AppError::Cancelled.is_connection_error());
+    }
+}
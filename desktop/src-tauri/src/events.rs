@@ -0,0 +1,257 @@
+//! Server push channel
+//!
+//! Keeps a Server-Sent Events stream open to `/api/v1/clipboard/events`. The
+//! server announces the index of every new item and the sync engine pulls
+//! right away instead of waiting for the next poll. Servers without the
+//! endpoint are detected and the engine keeps polling at its usual interval.
+
+use crate::error::AppError;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// How long to wait before asking a server without the endpoint again
+const UNSUPPORTED_RETRY: Duration = Duration::from_secs(10 * 60);
+
+/// How often to check whether the channel should be opened
+const IDLE_CHECK: Duration = Duration::from_secs(5);
+
+/// State of the push channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChannelState {
+    /// Turned off, or automatic sync is off or paused
+    Disabled,
+    /// Opening the stream or waiting to reconnect
+    Connecting,
+    /// Receiving announcements from the server
+    Connected,
+    /// The server has no push channel; polling only
+    Unsupported,
+}
+
+/// How a stream ended without an error
+enum Ended {
+    /// Closed by the server or because sync was turned off
+    Closed,
+    /// The server does not offer the endpoint
+    Unsupported,
+}
+
+/// Push channel shared between the listener and the sync engine
+pub struct PushChannel {
+    state: Mutex<ChannelState>,
+    wake: Notify,
+    reconnect: Notify,
+}
+
+impl Default for PushChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushChannel {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(ChannelState::Disabled),
+            wake: Notify::new(),
+            reconnect: Notify::new(),
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> ChannelState {
+        self.state
+            .lock()
+            .map(|state| *state)
+            .unwrap_or(ChannelState::Disabled)
+    }
+
+    /// Whether announcements are being received
+    pub fn is_connected(&self) -> bool {
+        self.state() == ChannelState::Connected
+    }
+
+    /// Wake the sync engine for an immediate pull
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Wait until the sync engine is woken
+    pub async fn woken(&self) {
+        self.wake.notified().await;
+    }
+
+    /// Drop the current stream and connect again, e.g. to another server
+    pub fn reconnect(&self) {
+        self.reconnect.notify_one();
+    }
+
+    /// Sleep, cut short by a reconnect request
+    async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.reconnect.notified() => {}
+        }
+    }
+
+    fn set_state(&self, next: ChannelState) {
+        if let Ok(mut state) = self.state.lock() {
+            if *state != next {
+                log::info!("Push channel: {:?} -> {:?}", *state, next);
+                *state = next;
+            }
+        }
+    }
+}
+
+/// Keep the push channel open for as long as the app runs
+pub async fn run_push_channel(app: AppHandle) {
+    let mut failures = 0;
+
+    loop {
+        let state = app.state::<crate::AppState>();
+        let channel = &state.sync_state.push_channel;
+        let (enabled, reconnect) = {
+            let config = state.config.lock().await;
+            (config.push_channel.enabled, config.reconnect.clone())
+        };
+
+        if !enabled || !state.sync_state.auto_sync_active() {
+            channel.set_state(ChannelState::Disabled);
+            channel.sleep(IDLE_CHECK).await;
+            continue;
+        }
+
+        if channel.state() != ChannelState::Connected {
+            channel.set_state(ChannelState::Connecting);
+        }
+
+        match listen(&app).await {
+            Ok(Ended::Closed) => {
+                failures = 0;
+                channel.set_state(ChannelState::Connecting);
+            }
+            Ok(Ended::Unsupported) => {
+                log::info!("Server has no push channel, polling instead");
+                channel.set_state(ChannelState::Unsupported);
+                channel.sleep(UNSUPPORTED_RETRY).await;
+                continue;
+            }
+            Err(e) => {
+                failures += 1;
+                log::debug!("Push channel failed: {}", e);
+                channel.set_state(ChannelState::Connecting);
+            }
+        }
+
+        // Pull once in case an announcement was missed while reconnecting
+        channel.wake();
+        channel.sleep(reconnect.delay(failures.max(1))).await;
+    }
+}
+
+/// Read announcements until the stream ends
+async fn listen(app: &AppHandle) -> Result<Ended, AppError> {
+    let state = app.state::<crate::AppState>();
+    let sync_state = &state.sync_state;
+
+    let url = sync_state.server_url();
+    let mut resp = sync_state
+        .client()
+        .get(format!("{}/api/v1/clipboard/events", url))
+        .header("Accept", "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| sync_state.pins.explain(&url, e.into()))?;
+    sync_state.pins.confirm(&url);
+
+    if matches!(resp.status().as_u16(), 404 | 405) {
+        return Ok(Ended::Unsupported);
+    }
+    if !resp.status().is_success() {
+        return Err(AppError::from_response(resp).await);
+    }
+
+    // Web frontends served from the same origin answer unknown paths with HTML
+    let is_event_stream = resp
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !is_event_stream {
+        return Ok(Ended::Unsupported);
+    }
+
+    sync_state.push_channel.set_state(ChannelState::Connected);
+
+    let mut buffer = Vec::new();
+    loop {
+        let chunk = tokio::select! {
+            chunk = resp.chunk() => chunk?,
+            _ = sync_state.push_channel.reconnect.notified() => break,
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+
+        while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = buffer.drain(..end + 2).collect();
+            if let Some(index) = parse_index(&String::from_utf8_lossy(&event)) {
+                if index != sync_state.last_server_index.load(Ordering::SeqCst) {
+                    log::debug!("Server announced index {}", index);
+                    sync_state.push_channel.wake();
+                }
+            }
+        }
+
+        // Hand back to polling rules when sync is switched off
+        if !sync_state.auto_sync_active() {
+            break;
+        }
+    }
+
+    Ok(Ended::Closed)
+}
+
+/// Index announced by an `index` event, ignoring comments and other events
+fn parse_index(event: &str) -> Option<u64> {
+    let mut name = "message";
+    let mut data = None;
+
+    for line in event.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            name = value.trim();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data = Some(value.trim());
+        }
+    }
+
+    (name == "index").then_some(data?)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_index_events() {
+        assert_eq!(parse_index("event: index\ndata: 42"), Some(42));
+        assert_eq!(parse_index("event:index\ndata:7"), Some(7));
+        assert_eq!(parse_index("id: 3\nevent: index\ndata: 3\n"), Some(3));
+    }
+
+    #[test]
+    fn ignores_comments_and_other_events() {
+        assert_eq!(parse_index(": keep-alive"), None);
+        assert_eq!(parse_index("data: 42"), None);
+        assert_eq!(parse_index("event: hello\ndata: 42"), None);
+        assert_eq!(parse_index("event: index"), None);
+        assert_eq!(parse_index("event: index\ndata: soon"), None);
+    }
+}
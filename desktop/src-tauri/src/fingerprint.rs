@@ -0,0 +1,140 @@
+//! Content fingerprints
+//!
+//! Every clipboard item is identified by a BLAKE3 hash of its type and
+//! bytes. Unlike `DefaultHasher` the result is stable across builds and
+//! platforms, so fingerprints can be persisted and compared between devices.
+
+use crate::clipboard::ClipboardContent;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Key derivation context, so fingerprints never collide with other uses
+/// of BLAKE3 on the same data
+const CONTEXT: &str = "gcopy 2024-01-01 clipboard content fingerprint v1";
+
+/// BLAKE3 fingerprint of a clipboard item, hex encoded when serialized
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint([u8; blake3::OUT_LEN]);
+
+impl Fingerprint {
+    /// Fingerprint of a payload of the given server content type
+    pub fn of_payload(content_type: &str, data: &[u8]) -> Self {
+        let mut hasher = blake3::Hasher::new_derive_key(CONTEXT);
+        hasher.update(content_type.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(data);
+        Self(*hasher.finalize().as_bytes())
+    }
+
+    /// Fingerprint of clipboard content; equal to the fingerprint of the
+    /// same content as a server payload
+    pub fn of(content: &ClipboardContent) -> Self {
+        match content {
+            ClipboardContent::Text(text) => Self::of_payload("text", text.as_bytes()),
+            ClipboardContent::Image(data) => Self::of_payload("screenshot", data),
+        }
+    }
+
+    /// Hex encoding of the fingerprint
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Parse a hex-encoded fingerprint
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let mut bytes = [0; blake3::OUT_LEN];
+        hex::decode_to_slice(hex, &mut bytes).ok()?;
+        Some(Self(bytes))
+    }
+
+    /// Abbreviated form for logs
+    pub fn short(&self) -> String {
+        hex::encode(&self.0[..6])
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fingerprint({})", self.short())
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Serialize for Fingerprint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Fingerprint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Self::from_hex(&hex).ok_or_else(|| serde::de::Error::custom("invalid fingerprint"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_answer() {
+        // Fingerprints are persisted and compared between devices, so the
+        // derivation must never change silently
+        assert_eq!(
+            Fingerprint::of_payload("text", b"hello world").to_hex(),
+            "56dc41f0e6ec34855ee8a2d07714266fb59fdc4fca5cb90d43d6515ffa71600e"
+        );
+    }
+
+    #[test]
+    fn type_is_part_of_the_fingerprint() {
+        assert_ne!(
+            Fingerprint::of_payload("text", b"data"),
+            Fingerprint::of_payload("screenshot", b"data")
+        );
+        // The separator keeps type and data apart
+        assert_ne!(
+            Fingerprint::of_payload("text", b"xdata"),
+            Fingerprint::of_payload("textx", b"data")
+        );
+        assert_eq!(
+            Fingerprint::of(&ClipboardContent::Text("data".into())),
+            Fingerprint::of_payload("text", b"data")
+        );
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let fingerprint = Fingerprint::of_payload("screenshot", b"\x89PNG");
+        let hex = fingerprint.to_hex();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(Fingerprint::from_hex(&hex), Some(fingerprint));
+        assert_eq!(
+            Fingerprint::from_hex(&hex.to_uppercase()),
+            Some(fingerprint)
+        );
+        assert!(hex.starts_with(&fingerprint.short()));
+
+        let json = serde_json::to_string(&fingerprint).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex));
+        assert_eq!(
+            serde_json::from_str::<Fingerprint>(&json).unwrap(),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        assert_eq!(Fingerprint::from_hex(""), None);
+        assert_eq!(Fingerprint::from_hex(&"0".repeat(62)), None);
+        assert_eq!(Fingerprint::from_hex(&"0".repeat(66)), None);
+        assert_eq!(Fingerprint::from_hex(&"g".repeat(64)), None);
+        assert!(serde_json::from_str::<Fingerprint>("\"abc\"").is_err());
+    }
+}
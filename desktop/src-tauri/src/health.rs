@@ -0,0 +1,279 @@
+//! Server health check
+//!
+//! Verifies a server URL before it is saved: whether it answers, how fast,
+//! which certificate it presents, which version it runs and whether the
+//! current session is logged in there.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Oldest server release the client works with: the first one with the
+/// push channel, `X-CreatedAt`, `X-ClientName` and `maxContentLength`
+const MIN_SERVER_VERSION: (u64, u64, u64) = (1, 5, 0);
+
+/// How long each check request may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS details of an HTTPS server
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsDetails {
+    /// SHA-256 fingerprint of the leaf certificate, hex encoded
+    pub certificate_sha256: Option<String>,
+}
+
+/// Result of checking a server
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCheck {
+    pub url: String,
+    /// `/api/v1/ping` answered
+    pub reachable: bool,
+    /// Round trip of the ping in milliseconds
+    pub latency_ms: Option<u64>,
+    pub tls: Option<TlsDetails>,
+    /// Version reported by the server, if any
+    pub version: Option<String>,
+    /// Whether the client supports the server version; `None` when unknown
+    pub compatible: Option<bool>,
+    /// Maximum body size in bytes, if reported
+    pub max_content_length: Option<u64>,
+    pub logged_in: bool,
+    pub account: Option<String>,
+    pub warnings: Vec<String>,
+    /// Why the server could not be reached
+    pub error: Option<AppError>,
+}
+
+/// Fields of `/api/v1/systeminfo` shown in the check
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    version: Option<String>,
+    max_content_length: Option<u64>,
+}
+
+/// Check the server at `url`
+pub async fn check(app: &AppHandle, url: &str) -> ServerCheck {
+    let url = url.trim_end_matches('/').to_string();
+    let mut result = ServerCheck {
+        url: url.clone(),
+        reachable: false,
+        latency_ms: None,
+        tls: None,
+        version: None,
+        compatible: None,
+        max_content_length: None,
+        logged_in: false,
+        account: None,
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    let state = app.state::<crate::AppState>();
+    let (network, access, user_agent) = {
+        let config = state.config.lock().await;
+        (
+            config.network.clone(),
+            config.access_for(&url).cloned(),
+            crate::device::user_agent(&config.device_name()),
+        )
+    };
+    let pins = &state.sync_state.pins;
+    let client = crate::network::client_builder(
+        &network,
+        access.as_ref(),
+        &user_agent,
+        state.sync_state.cookies.clone(),
+        pins.clone(),
+    )
+    .and_then(|builder| {
+        builder
+            .tls_info(true)
+            .timeout(CHECK_TIMEOUT)
+            .build()
+            .map_err(AppError::from)
+    });
+    let client = match client {
+        Ok(client) => client,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+
+    // Reachability and latency
+    let started = Instant::now();
+    let resp = match client.get(format!("{}/api/v1/ping", url)).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            result.error = Some(pins.explain(&url, e.into()));
+            return result;
+        }
+    };
+    pins.confirm(&url);
+    result.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    if url.starts_with("https://") {
+        let certificate = resp
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|info| info.peer_certificate())
+            .map(|der| hex::encode(Sha256::digest(der)));
+        result.tls = Some(TlsDetails {
+            certificate_sha256: certificate,
+        });
+    } else {
+        result
+            .warnings
+            .push("The connection is not encrypted; use HTTPS outside a trusted network".into());
+    }
+
+    if !resp.status().is_success() {
+        result.error = Some(AppError::from_response(resp).await);
+        return result;
+    }
+    if resp.text().await.unwrap_or_default().trim() != "pong" {
+        result.error = Some(AppError::Protocol(
+            "The URL does not point to a GCopy server".into(),
+        ));
+        return result;
+    }
+    result.reachable = true;
+
+    // Version and limits
+    match client
+        .get(format!("{}/api/v1/systeminfo", url))
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => {
+            if let Ok(info) = resp.json::<SystemInfo>().await {
+                result.version = info.version.filter(|v| parse_version(v).is_some());
+                result.max_content_length = info.max_content_length.filter(|&limit| limit > 0);
+            }
+        }
+        Ok(resp) => log::debug!("System info failed: {}", resp.status()),
+        Err(e) => log::debug!("System info failed: {}", e),
+    }
+
+    match result.version.as_deref().and_then(parse_version) {
+        Some(version) => {
+            let compatible = is_compatible(version);
+            if !compatible {
+                result.warnings.push(format!(
+                    "Server version {} is not supported; version {}.{}.x or newer 1.x is required",
+                    result.version.as_deref().unwrap_or_default(),
+                    MIN_SERVER_VERSION.0,
+                    MIN_SERVER_VERSION.1
+                ));
+            }
+            result.compatible = Some(compatible);
+        }
+        None => result.warnings.push(
+            "The server does not report its version; it may be too old for some features".into(),
+        ),
+    }
+
+    // Login state of the current session
+    if let Ok(resp) = client.get(format!("{}/api/v1/user", url)).send().await {
+        if resp.status().is_success() {
+            if let Ok(user) = resp.json::<crate::auth::ServerUser>().await {
+                result.logged_in = user.logged_in;
+                result.account = Some(user.email).filter(|_| user.logged_in);
+            }
+        }
+    }
+
+    result
+}
+
+/// Whether the client works with a server version
+fn is_compatible(version: (u64, u64, u64)) -> bool {
+    version.0 == MIN_SERVER_VERSION.0 && version >= MIN_SERVER_VERSION
+}
+
+/// Major, minor and patch of a version such as `v1.4.9`
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map(|part| part.parse::<u64>());
+    Some((
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next().and_then(Result::ok).unwrap_or(0),
+    ))
+}
+
+/// Check the configured server and reflect a failure in the tray
+pub async fn check_configured_server(app: &AppHandle) -> ServerCheck {
+    let state = app.state::<crate::AppState>();
+    let url = state.config.lock().await.server_url.clone();
+    let result = check(app, &url).await;
+    crate::tray::set_server_problem(app, problem(&result).as_deref());
+    result
+}
+
+/// Short description of what is wrong, for the tray
+fn problem(result: &ServerCheck) -> Option<String> {
+    if let Some(e) = &result.error {
+        return Some(e.to_string());
+    }
+    if result.compatible == Some(false) {
+        return Some(format!(
+            "Unsupported server version {}",
+            result.version.as_deref().unwrap_or_default()
+        ));
+    }
+    None
+}
+
+/// Tauri command: Check a server before using it
+#[tauri::command]
+pub async fn check_server(app: AppHandle, url: Option<String>) -> ServerCheck {
+    let state = app.state::<crate::AppState>();
+    let configured = state.config.lock().await.server_url.clone();
+    let url = url.unwrap_or_else(|| configured.clone());
+
+    // Only the server in use is reflected in the tray
+    if url.trim_end_matches('/') == configured.trim_end_matches('/') {
+        return check_configured_server(&app).await;
+    }
+    check(&app, &url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(parse_version("v1.4.9"), Some((1, 4, 9)));
+        assert_eq!(parse_version(" 2.0.1\n"), Some((2, 0, 1)));
+        assert_eq!(parse_version("1.5"), Some((1, 5, 0)));
+        assert_eq!(parse_version("1.5.0-beta.2"), Some((1, 5, 0)));
+        assert_eq!(parse_version("1.5.3+abc"), Some((1, 5, 3)));
+    }
+
+    #[test]
+    fn requires_this_release_series() {
+        assert!(!is_compatible((1, 4, 9)));
+        assert!(is_compatible((1, 5, 0)));
+        assert!(is_compatible((1, 6, 2)));
+        assert!(!is_compatible((2, 0, 0)));
+        assert!(!is_compatible((0, 9, 0)));
+    }
+
+    #[test]
+    fn rejects_malformed_versions() {
+        assert_eq!(parse_version(""), None);
+        assert_eq!(parse_version("dev"), None);
+        assert_eq!(parse_version("1"), None);
+        assert_eq!(parse_version("1.x.0"), None);
+    }
+}
@@ -2,14 +2,52 @@
 //!
 //! This module provides the Tauri commands and state management for the desktop app.
 
+mod auth;
 mod clipboard;
+mod compression;
 mod config;
+mod conflict;
+mod connection;
+mod crypto;
+mod cursor;
+mod device;
+mod error;
+mod events;
+mod fingerprint;
+mod health;
+mod limits;
+mod network;
+mod notifications;
+mod outbox;
+mod pairing;
+mod pause;
+mod pinning;
+mod recent;
+mod schedule;
+mod secrets;
+mod shortcuts;
 mod sync;
+mod transfer;
 mod tray;
 
 pub use clipboard::*;
 pub use config::*;
+pub use conflict::*;
+pub use connection::*;
+pub use cursor::*;
+pub use error::*;
+pub use events::*;
+pub use fingerprint::*;
+pub use health::*;
+pub use limits::*;
+pub use outbox::*;
+pub use pairing::*;
+pub use pause::*;
+pub use pinning::*;
+pub use recent::*;
+pub use schedule::*;
 pub use sync::*;
+pub use transfer::*;
 pub use tray::*;
 
 use std::sync::Arc;
@@ -22,10 +60,16 @@ pub struct AppState {
     pub sync_state: Arc<SyncState>,
 }
 
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AppState {
     pub fn new() -> Self {
         let config = AppConfig::load().unwrap_or_default();
-        let sync_state = SyncState::new(config.server_url.clone());
+        let sync_state = SyncState::new(&config);
 
         Self {
             config: Arc::new(Mutex::new(config)),
@@ -42,6 +86,7 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--minimized"]),
@@ -54,6 +99,10 @@ pub fn run() {
             // Setup system tray
             tray::setup_tray(app.handle())?;
 
+            // Register global shortcuts
+            let config = AppConfig::load().unwrap_or_default();
+            shortcuts::register_shortcuts(app.handle(), &config.shortcuts);
+
             // Start clipboard monitoring
             let handle = app.handle().clone();
             std::thread::spawn(move || {
@@ -66,20 +115,58 @@ pub fn run() {
                 sync::start_background_sync(handle).await;
             });
 
+            // Listen for items announced by the server
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                events::run_push_channel(handle).await;
+            });
+
             log::info!("GCopy Desktop started successfully");
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Catch up as soon as the user comes back to the app
+            if let tauri::WindowEvent::Focused(true) = event {
+                let state = window.state::<AppState>();
+                state.sync_state.schedule.activity();
+                state.sync_state.push_channel.wake();
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             // Clipboard commands
             clipboard::read_clipboard,
             clipboard::write_clipboard,
+            // Account commands
+            auth::request_login_code,
+            auth::login,
+            auth::logout,
+            auth::get_user,
             // Config commands
             config::get_config,
             config::save_config,
+            health::check_server,
+            pinning::get_certificate_pins,
+            pinning::reset_certificate_pins,
+            // Conflict commands
+            conflict::get_pending_conflict,
+            conflict::resolve_conflict,
+            // Pairing commands
+            pairing::create_pairing,
+            pairing::import_pairing,
+            pairing::rotate_pairing_key,
+            pairing::unpair,
+            pairing::get_pairing_status,
             // Sync commands
             sync::sync_now,
             sync::toggle_auto_sync,
+            pause::pause_auto_sync,
+            pause::resume_auto_sync,
             sync::get_sync_status,
+            sync::get_outbox,
+            sync::cancel_outbox_item,
+            sync::clear_outbox,
+            recent::get_recent_items,
+            transfer::cancel_transfer,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
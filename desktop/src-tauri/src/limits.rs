@@ -0,0 +1,161 @@
+//! Server limits
+//!
+//! The server rejects bodies over its `max-content-length` with 413. The
+//! limit is learned from `/api/v1/systeminfo`, or from the message of a 413
+//! response on servers that do not report it, so oversized payloads are
+//! refused before they are uploaded.
+
+use crate::error::AppError;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Manager};
+
+/// Content length limit of the server, when known
+#[derive(Default)]
+pub struct ServerLimits {
+    /// Maximum body size in bytes; 0 while unknown
+    max_content_length: AtomicU64,
+}
+
+impl ServerLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum body size in bytes, if known
+    pub fn max_content_length(&self) -> Option<u64> {
+        match self.max_content_length.load(Ordering::SeqCst) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    pub(crate) fn set_max_content_length(&self, limit: u64) {
+        let previous = self.max_content_length.swap(limit, Ordering::SeqCst);
+        if previous != limit {
+            log::info!("Server content length limit: {} bytes", limit);
+        }
+    }
+
+    /// Refuse a payload of `size` bytes that the server would reject
+    pub fn check(&self, size: u64) -> Result<(), AppError> {
+        match self.max_content_length() {
+            Some(limit) if size > limit => Err(AppError::TooLarge {
+                message: format!(
+                    "Content is {} but the server accepts at most {}",
+                    format_size(size),
+                    format_size(limit)
+                ),
+                size: Some(size),
+                limit: Some(limit),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Learn the limit from a 413 error and attach the payload size to it
+    pub(crate) fn learn(&self, error: AppError, size: u64) -> AppError {
+        let AppError::TooLarge { message, limit, .. } = error else {
+            return error;
+        };
+
+        let limit = limit.or_else(|| parse_limit(&message));
+        if let Some(limit) = limit {
+            self.set_max_content_length(limit);
+        }
+
+        AppError::TooLarge {
+            message,
+            size: Some(size),
+            limit,
+        }
+    }
+}
+
+/// Fields of `/api/v1/systeminfo` the client uses
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    /// Maximum body size in bytes; missing on older servers
+    max_content_length: Option<u64>,
+}
+
+/// Refresh the content length limit from the server.
+///
+/// Failures are not fatal; the limit is then learned from the first 413.
+pub async fn refresh_limits(app: &AppHandle) {
+    let state = app.state::<crate::AppState>();
+    let server_url = state.config.lock().await.server_url.clone();
+
+    let resp = state
+        .sync_state
+        .client()
+        .get(format!("{}/api/v1/systeminfo", server_url))
+        .send()
+        .await;
+
+    let info = match resp {
+        Ok(resp) if resp.status().is_success() => resp.json::<SystemInfo>().await,
+        Ok(resp) => {
+            log::debug!("Failed to fetch system info: {}", resp.status());
+            return;
+        }
+        Err(e) => Err(e),
+    };
+
+    match info {
+        Ok(SystemInfo {
+            max_content_length: Some(limit),
+        }) if limit > 0 => state.sync_state.limits.set_max_content_length(limit),
+        Ok(_) => log::debug!("Server does not report a content length limit"),
+        Err(e) => log::debug!("Failed to fetch system info: {}", e),
+    }
+}
+
+/// Limit in bytes from a message such as "... cannot exceed 10MiB"
+fn parse_limit(message: &str) -> Option<u64> {
+    let end = message.rfind("MiB")?;
+    let digits = message[..end].trim_end();
+    let start = digits
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    let mib: u64 = digits[start..].parse().ok()?;
+    Some(mib * 1024 * 1024)
+}
+
+/// Human-readable size
+pub fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes / MIB)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes / KIB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_limit_from_server_message() {
+        assert_eq!(
+            parse_limit("Content length cannot exceed 10MiB"),
+            Some(10 * 1024 * 1024)
+        );
+        assert_eq!(parse_limit("limit: 256 MiB"), Some(256 * 1024 * 1024));
+        assert_eq!(parse_limit("5MiB"), Some(5 * 1024 * 1024));
+    }
+
+    #[test]
+    fn rejects_messages_without_a_limit() {
+        assert_eq!(parse_limit("Request too large"), None);
+        assert_eq!(parse_limit("cannot exceed MiB"), None);
+        assert_eq!(parse_limit("cannot exceed 10KiB"), None);
+    }
+}
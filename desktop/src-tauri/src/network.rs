@@ -0,0 +1,157 @@
+//! HTTP client setup
+//!
+//! Builds the client used for all server requests from the network settings:
+//! proxy, extra trusted CA certificates or pinned certificates (not both), an
+//! optional client certificate for mutual TLS, and timeouts.
+
+use crate::config::{CertificateTrust, NetworkConfig, ServerAccess};
+use crate::error::AppError;
+use crate::pinning::PinStore;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, ClientBuilder, Identity, NoProxy, Proxy};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Client builder configured from the network settings and the reverse
+/// proxy settings of the server
+pub fn client_builder(
+    config: &NetworkConfig,
+    access: Option<&ServerAccess>,
+    user_agent: &str,
+    cookies: Arc<Jar>,
+    pins: Arc<PinStore>,
+) -> Result<ClientBuilder, AppError> {
+    let mut builder = reqwest::Client::builder()
+        .cookie_provider(cookies)
+        .user_agent(user_agent)
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs.max(1)));
+
+    if config.read_timeout_secs > 0 {
+        builder = builder.read_timeout(Duration::from_secs(config.read_timeout_secs));
+    }
+
+    if let Some(access) = access {
+        builder = builder.default_headers(access_headers(access)?);
+    }
+
+    // Without an explicit proxy the system settings apply
+    let proxy = config.proxy.trim();
+    if !proxy.is_empty() {
+        let proxy = Proxy::all(proxy)
+            .map_err(|e| AppError::Config(format!("Invalid proxy {}: {}", proxy, e)))?
+            .no_proxy(NoProxy::from_string(&config.no_proxy));
+        builder = builder.proxy(proxy);
+    }
+
+    if config.certificate_trust == CertificateTrust::TrustOnFirstUse {
+        // Pinning replaces CA verification, so extra CAs would be ignored
+        if config.ca_certificates.iter().any(|path| !path.is_empty()) {
+            return Err(AppError::Config(
+                "CA certificates cannot be combined with trust on first use".into(),
+            ));
+        }
+        let client_auth = if config.client_certificate.is_empty() {
+            None
+        } else {
+            Some(client_auth(config)?)
+        };
+        let tls = crate::pinning::tls_config(pins, client_auth)?;
+        return Ok(builder.use_preconfigured_tls(tls));
+    }
+
+    for path in config
+        .ca_certificates
+        .iter()
+        .filter(|path| !path.is_empty())
+    {
+        let pem = read(path, "CA certificate")?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| AppError::Config(format!("Invalid CA certificate {}: {}", path, e)))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if !config.client_certificate.is_empty() {
+        let certificate = read(&config.client_certificate, "client certificate")?;
+        let key = read(&config.client_key, "client key")?;
+        let identity = Identity::from_pkcs8_pem(&certificate, &key).map_err(|e| {
+            AppError::Config(format!(
+                "Invalid client certificate {}: {}",
+                config.client_certificate, e
+            ))
+        })?;
+        builder = builder.identity(identity);
+    }
+
+    Ok(builder)
+}
+
+/// Build the client used for server requests
+pub fn build_client(
+    config: &NetworkConfig,
+    access: Option<&ServerAccess>,
+    user_agent: &str,
+    cookies: Arc<Jar>,
+    pins: Arc<PinStore>,
+) -> Result<reqwest::Client, AppError> {
+    client_builder(config, access, user_agent, cookies, pins)?
+        .build()
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Headers sent with every request to a server behind a reverse proxy
+fn access_headers(access: &ServerAccess) -> Result<HeaderMap, AppError> {
+    let mut headers = HeaderMap::new();
+
+    for header in access.headers.iter().filter(|h| !h.name.trim().is_empty()) {
+        let name = HeaderName::from_bytes(header.name.trim().as_bytes())
+            .map_err(|e| AppError::Config(format!("Invalid header name {}: {}", header.name, e)))?;
+        let mut value = HeaderValue::from_str(&header.value).map_err(|e| {
+            AppError::Config(format!("Invalid value of header {}: {}", header.name, e))
+        })?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+
+    if !access.username.is_empty() {
+        let credentials = STANDARD.encode(format!("{}:{}", access.username, access.password));
+        let mut value = HeaderValue::from_str(&format!("Basic {}", credentials))
+            .map_err(|e| AppError::Config(e.to_string()))?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+
+    Ok(headers)
+}
+
+/// Client certificate chain and key for the pinning TLS settings
+fn client_auth(
+    config: &NetworkConfig,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), AppError> {
+    let invalid = |e: rustls::pki_types::pem::Error| {
+        AppError::Config(format!(
+            "Invalid client certificate {}: {}",
+            config.client_certificate, e
+        ))
+    };
+
+    let certificate = read(&config.client_certificate, "client certificate")?;
+    let certificates = CertificateDer::pem_slice_iter(&certificate)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+    let key =
+        PrivateKeyDer::from_pem_slice(&read(&config.client_key, "client key")?).map_err(invalid)?;
+
+    Ok((certificates, key))
+}
+
+fn read(path: &str, what: &str) -> Result<Vec<u8>, AppError> {
+    std::fs::read(path)
+        .map_err(|e| AppError::Config(format!("Failed to read {} {}: {}", what, path, e)))
+}
@@ -0,0 +1,432 @@
+//! Desktop notifications
+//!
+//! Native notifications for received items, failed pushes, an expired
+//! session, oversized items and conflicts. Each kind can be turned off in
+//! the settings, and "do not disturb" silences all of them.
+
+use crate::clipboard::ClipboardContent;
+use crate::config::NotificationConfig;
+use crate::error::AppError;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// Characters of received text shown in a notification
+const PREVIEW_LEN: usize = 120;
+
+/// Longest side of image thumbnails in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// How long a thumbnail is kept after its notification was shown
+const THUMBNAIL_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Kinds of notifications, each with its own toggle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    Received,
+    PushFailed,
+    AuthExpired,
+    TooLarge,
+    Conflict,
+}
+
+impl NotificationConfig {
+    /// Whether notifications of this kind are shown
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        !self.do_not_disturb
+            && match kind {
+                NotificationKind::Received => self.received,
+                NotificationKind::PushFailed => self.push_failed,
+                NotificationKind::AuthExpired => self.auth_expired,
+                NotificationKind::TooLarge => self.too_large,
+                NotificationKind::Conflict => self.conflict,
+            }
+    }
+}
+
+/// Notification ready to be shown
+pub struct Notice {
+    title: String,
+    body: String,
+    icon: Option<PathBuf>,
+}
+
+impl Notice {
+    pub fn show(self, app: &AppHandle) {
+        let mut builder = app
+            .notification()
+            .builder()
+            .title(self.title)
+            .body(self.body);
+        if let Some(icon) = self.icon {
+            builder = builder.icon(icon.to_string_lossy());
+        }
+        if let Err(e) = builder.show() {
+            log::warn!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+/// Show a notification unless its kind is turned off
+pub(crate) fn notify(app: &AppHandle, kind: NotificationKind, title: String, body: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<crate::AppState>();
+        if state.config.lock().await.notifications.allows(kind) {
+            Notice {
+                title,
+                body,
+                icon: None,
+            }
+            .show(&app);
+        }
+    });
+}
+
+/// Notify about an item pulled into the clipboard, if enabled
+pub(crate) fn received(app: &AppHandle, content: ClipboardContent, device: Option<String>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let config = {
+            let state = app.state::<crate::AppState>();
+            let config = state.config.lock().await;
+            config.notifications.clone()
+        };
+        if !config.allows(NotificationKind::Received) {
+            return;
+        }
+
+        // Decoding and scaling a screenshot is too slow for the async runtime
+        let notice = tauri::async_runtime::spawn_blocking(move || {
+            received_notice(
+                &content,
+                device.as_deref(),
+                config.previews,
+                &thumbnails_dir(),
+            )
+        })
+        .await;
+        let notice = match notice {
+            Ok(notice) => notice,
+            Err(e) => {
+                log::warn!("Failed to prepare notification: {}", e);
+                return;
+            }
+        };
+
+        let thumbnail = notice.icon.clone();
+        notice.show(&app);
+
+        // The notification server loads the icon when showing it
+        if let Some(path) = thumbnail {
+            tokio::time::sleep(THUMBNAIL_LIFETIME).await;
+            if let Err(e) = fs::remove_file(&path) {
+                log::debug!("Failed to remove thumbnail {}: {}", path.display(), e);
+            }
+        }
+    });
+}
+
+/// Notification for a received item, with a preview when `previews` is on
+fn received_notice(
+    content: &ClipboardContent,
+    device: Option<&str>,
+    previews: bool,
+    thumbnails: &Path,
+) -> Notice {
+    let what = match content {
+        ClipboardContent::Text(_) => type_label("text"),
+        ClipboardContent::Image(_) => type_label("screenshot"),
+    };
+    let title = match device {
+        Some(device) => format!("{} 来自 from {}", what, device),
+        None => format!("{} 已接收 received", what),
+    };
+
+    let mut notice = Notice {
+        title,
+        body: "已复制到剪贴板 Copied to your clipboard".into(),
+        icon: None,
+    };
+    if !previews {
+        return notice;
+    }
+
+    match content {
+        ClipboardContent::Text(text) => {
+            let preview = preview(text);
+            if !preview.is_empty() {
+                notice.body = preview;
+            }
+        }
+        ClipboardContent::Image(data) => match thumbnail(data, thumbnails) {
+            Ok((width, height, path)) => {
+                notice.body = format!(
+                    "{}×{} 图片已复制到剪贴板 image copied to your clipboard",
+                    width, height
+                );
+                notice.icon = Some(path);
+            }
+            Err(e) => log::debug!("No thumbnail for notification: {}", e),
+        },
+    }
+
+    notice
+}
+
+/// Text on one line, cut to `PREVIEW_LEN` characters
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut preview: String = text.chars().take(PREVIEW_LEN).collect();
+    if preview.len() < text.len() {
+        preview.push('…');
+    }
+    preview
+}
+
+/// Notify about a failed push the user would not learn about otherwise
+pub(crate) fn push_failed(app: &AppHandle, error: &AppError) {
+    // Connection problems are retried and shown as the connection state,
+    // and oversized items have their own notification
+    if error.is_connection_error()
+        || matches!(error, AppError::TooLarge { .. } | AppError::Cancelled)
+    {
+        return;
+    }
+    notify(
+        app,
+        NotificationKind::PushFailed,
+        "GCopy: 推送失败 push failed".into(),
+        error.to_string(),
+    );
+}
+
+/// Notify that the server no longer accepts the session
+pub(crate) fn auth_expired(app: &AppHandle) {
+    notify(
+        app,
+        NotificationKind::AuthExpired,
+        "GCopy: 已登出 logged out".into(),
+        "服务器不再接受此会话，请重新登录 The server no longer accepts this session. Log in again to keep syncing.".into(),
+    );
+}
+
+/// Notify that an item was too large to sync
+pub(crate) fn too_large(app: &AppHandle, content_type: &str, error: &AppError) {
+    notify(
+        app,
+        NotificationKind::TooLarge,
+        format!(
+            "GCopy: {} 过大无法同步 too large to sync",
+            type_label(content_type)
+        ),
+        error.to_string(),
+    );
+}
+
+/// Bilingual label of a content type, like the tray menu
+pub(crate) fn type_label(content_type: &str) -> &str {
+    match content_type {
+        "text" => "文本 Text",
+        "screenshot" => "截图 Screenshot",
+        "file" => "文件 File",
+        other => other,
+    }
+}
+
+/// Directory notification thumbnails are written to
+fn thumbnails_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("gcopy")
+        .join("notifications")
+}
+
+/// Downscale a PNG and write it to a file of its own in `dir`, where the
+/// notification can load it; returns the original size and the thumbnail path
+fn thumbnail(png_data: &[u8], dir: &Path) -> Result<(u32, u32, PathBuf), AppError> {
+    let invalid = |e: png::DecodingError| AppError::Clipboard(format!("Invalid image: {}", e));
+    let mut decoder = png::Decoder::new(Cursor::new(png_data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(invalid)?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => {
+            return Err(AppError::Clipboard("Unexpanded palette image".into()))
+        }
+    };
+
+    // Nearest-neighbor scaling is plenty for a notification icon
+    let (width, height) = (info.width, info.height);
+    let scale = (THUMBNAIL_SIZE as f64 / width.max(height) as f64).min(1.0);
+    let thumb_width = ((width as f64 * scale) as u32).max(1);
+    let thumb_height = ((height as f64 * scale) as u32).max(1);
+
+    let mut rgba = Vec::with_capacity((thumb_width * thumb_height * 4) as usize);
+    for y in 0..thumb_height {
+        let source_y = (y as u64 * height as u64 / thumb_height as u64) as usize;
+        for x in 0..thumb_width {
+            let source_x = (x as u64 * width as u64 / thumb_width as u64) as usize;
+            let offset = source_y * info.line_size + source_x * channels;
+            let pixel = &buf[offset..offset + channels];
+            match channels {
+                1 => rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], 255]),
+                2 => rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], pixel[1]]),
+                3 => rgba.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]),
+                _ => rgba.extend_from_slice(pixel),
+            }
+        }
+    }
+
+    let mut encoded = Vec::new();
+    {
+        let encode = |e: png::EncodingError| AppError::Clipboard(e.to_string());
+        let mut encoder = png::Encoder::new(&mut encoded, thumb_width, thumb_height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(encode)?;
+        writer.write_image_data(&rgba).map_err(encode)?;
+    }
+
+    fs::create_dir_all(dir).map_err(|e| AppError::File(e.to_string()))?;
+    remove_stale_thumbnails(dir);
+    let path = dir.join(format!("{:016x}.png", rand::random::<u64>()));
+    fs::write(&path, encoded).map_err(|e| AppError::File(e.to_string()))?;
+
+    Ok((width, height, path))
+}
+
+/// Remove thumbnails left behind by notifications of an earlier run
+fn remove_stale_thumbnails(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > THUMBNAIL_LIFETIME * 2);
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&vec![200; (width * height * 3) as usize])
+            .unwrap();
+        writer.finish().unwrap();
+        data
+    }
+
+    fn size_of(path: &Path) -> (u32, u32) {
+        let decoder = png::Decoder::new(Cursor::new(fs::read(path).unwrap()));
+        let info = decoder.read_info().unwrap();
+        (info.info().width, info.info().height)
+    }
+
+    #[test]
+    fn kinds_follow_their_toggle() {
+        let all = [
+            NotificationKind::Received,
+            NotificationKind::PushFailed,
+            NotificationKind::AuthExpired,
+            NotificationKind::TooLarge,
+            NotificationKind::Conflict,
+        ];
+        let config = NotificationConfig::default();
+        assert!(all.iter().all(|&kind| config.allows(kind)));
+
+        let config = NotificationConfig {
+            push_failed: false,
+            ..Default::default()
+        };
+        assert!(!config.allows(NotificationKind::PushFailed));
+        assert!(config.allows(NotificationKind::Received));
+    }
+
+    #[test]
+    fn do_not_disturb_silences_everything() {
+        let config = NotificationConfig {
+            do_not_disturb: true,
+            ..Default::default()
+        };
+        assert!(!config.allows(NotificationKind::Received));
+        assert!(!config.allows(NotificationKind::Conflict));
+        assert!(!config.allows(NotificationKind::AuthExpired));
+    }
+
+    #[test]
+    fn previews_are_one_line_and_truncated() {
+        assert_eq!(preview("  hello\n\tworld  "), "hello world");
+        assert_eq!(preview(" \n "), "");
+
+        let exact = "a".repeat(PREVIEW_LEN);
+        assert_eq!(preview(&exact), exact);
+
+        let long = "界".repeat(PREVIEW_LEN + 1);
+        let cut = preview(&long);
+        assert_eq!(cut.chars().count(), PREVIEW_LEN + 1);
+        assert!(cut.ends_with('…'));
+        assert!(cut.starts_with(&"界".repeat(PREVIEW_LEN)));
+    }
+
+    #[test]
+    fn previews_can_be_turned_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = ClipboardContent::Text("secret".into());
+
+        let notice = received_notice(&content, Some("laptop"), false, dir.path());
+        assert!(notice.title.contains("laptop"));
+        assert!(!notice.body.contains("secret"));
+
+        let notice = received_notice(&content, None, true, dir.path());
+        assert_eq!(notice.body, "secret");
+    }
+
+    #[test]
+    fn thumbnails_are_downscaled_to_files_of_their_own() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let (width, height, first) = thumbnail(&png(512, 128), dir.path()).unwrap();
+        assert_eq!((width, height), (512, 128));
+        assert_eq!(size_of(&first), (THUMBNAIL_SIZE, 64));
+
+        let (_, _, second) = thumbnail(&png(16, 8), dir.path()).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(size_of(&second), (16, 8));
+        assert!(first.exists());
+    }
+
+    #[test]
+    fn invalid_images_have_no_thumbnail() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = thumbnail(b"not a png", dir.path());
+        assert!(matches!(result, Err(AppError::Clipboard(_))));
+
+        let content = ClipboardContent::Image(b"not a png".to_vec());
+        let notice = received_notice(&content, None, true, dir.path());
+        assert!(notice.icon.is_none());
+    }
+}
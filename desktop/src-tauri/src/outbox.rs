@@ -0,0 +1,395 @@
+//! Persistent outbox
+//!
+//! Pushes that fail because the server is unreachable are queued on disk and
+//! retried in order once the connection comes back, so copies made while
+//! offline still reach other devices. Queued payloads are not yet end-to-end
+//! encrypted, so the queue is sealed with the local secrets file key.
+
+use crate::config::{CompressionAlgorithm, OutboxConfig};
+use crate::crypto::{self, PairedKey};
+use crate::error::AppError;
+use crate::fingerprint::Fingerprint;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Queued push, without its payload
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxItem {
+    pub id: u64,
+    pub content_type: String,
+    pub size: u64,
+    pub created_at: u64,
+    /// Compression the queued payload is stored with
+    #[serde(default)]
+    pub compression: CompressionAlgorithm,
+    /// Fingerprint of the content before compression and encryption
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+}
+
+/// Queue of pending pushes, mirrored to disk
+pub struct Outbox {
+    dir: PathBuf,
+    /// Key the queue is sealed with; without it nothing can be queued
+    key: Option<PairedKey>,
+    items: Mutex<Vec<OutboxItem>>,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self::load()
+    }
+}
+
+impl Outbox {
+    /// Load the queue left over from a previous run
+    pub fn load() -> Self {
+        let dir = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("gcopy")
+            .join("outbox");
+
+        match crate::secrets::file_key() {
+            Ok(key) => Self::open(dir, Some(key)),
+            Err(e) => {
+                log::error!("Outbox disabled, no key to seal it: {}", e);
+                Self::open(dir, None)
+            }
+        }
+    }
+
+    /// Load the queue stored in a directory
+    fn open(dir: PathBuf, key: Option<PairedKey>) -> Self {
+        let outbox = Self {
+            dir,
+            key,
+            items: Mutex::new(Vec::new()),
+        };
+
+        let items = outbox
+            .read_sealed(&outbox.dir.join("index.bin"))
+            .ok()
+            .and_then(|content| serde_json::from_slice::<Vec<OutboxItem>>(&content).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|item| outbox.payload_path(item.id).exists())
+            .collect();
+
+        Self {
+            items: Mutex::new(items),
+            ..outbox
+        }
+    }
+
+    /// Queued items, oldest first
+    pub fn items(&self) -> Vec<OutboxItem> {
+        self.items
+            .lock()
+            .map(|items| items.clone())
+            .unwrap_or_default()
+    }
+
+    /// Number of queued items
+    pub fn len(&self) -> usize {
+        self.items
+            .lock()
+            .map(|items| items.len())
+            .unwrap_or_default()
+    }
+
+    /// Whether nothing is queued
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queue a payload according to the outbox policy.
+    ///
+    /// Content identical to the newest queued item is not queued twice.
+    pub fn enqueue(
+        &self,
+        data: &[u8],
+        content_type: &str,
+        compression: CompressionAlgorithm,
+        fingerprint: Fingerprint,
+        policy: &OutboxConfig,
+    ) -> Result<OutboxItem, AppError> {
+        let mut items = self.lock()?;
+
+        if let Some(last) = items
+            .last()
+            .filter(|last| last.fingerprint == Some(fingerprint))
+        {
+            log::debug!("{} already queued as item {}", content_type, last.id);
+            return Ok(last.clone());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let item = OutboxItem {
+            id: items.last().map_or(now, |last| now.max(last.id + 1)),
+            content_type: content_type.to_string(),
+            size: data.len() as u64,
+            created_at: now / 1000,
+            compression,
+            fingerprint: Some(fingerprint),
+        };
+
+        self.write_sealed(&self.payload_path(item.id), data)?;
+
+        // Only the newest item per type matters when collapsing
+        if policy.collapse {
+            items.retain(|queued| {
+                let keep = queued.content_type != item.content_type;
+                if !keep {
+                    self.remove_payload(queued.id);
+                }
+                keep
+            });
+        }
+
+        items.push(item.clone());
+
+        while policy.max_items > 0 && items.len() > policy.max_items {
+            let dropped = items.remove(0);
+            self.remove_payload(dropped.id);
+            log::warn!("Outbox full, dropped item {}", dropped.id);
+        }
+
+        self.save(&items)?;
+        Ok(item)
+    }
+
+    /// Oldest queued item together with its payload
+    pub fn front(&self) -> Result<Option<(OutboxItem, Vec<u8>)>, AppError> {
+        let Some(item) = self.lock()?.first().cloned() else {
+            return Ok(None);
+        };
+
+        match self.read_sealed(&self.payload_path(item.id)) {
+            Ok(data) => Ok(Some((item, data))),
+            Err(e) => {
+                // Payload is gone or unreadable; drop the entry so the queue
+                // keeps moving
+                self.remove(item.id)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Remove an item; returns whether it was queued
+    pub fn remove(&self, id: u64) -> Result<bool, AppError> {
+        let mut items = self.lock()?;
+        let before = items.len();
+        items.retain(|item| item.id != id);
+        if items.len() == before {
+            return Ok(false);
+        }
+
+        self.remove_payload(id);
+        self.save(&items)?;
+        Ok(true)
+    }
+
+    /// Remove every item
+    pub fn clear(&self) -> Result<(), AppError> {
+        let mut items = self.lock()?;
+        for item in items.drain(..) {
+            self.remove_payload(item.id);
+        }
+        self.save(&items)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Vec<OutboxItem>>, AppError> {
+        self.items.lock().map_err(|e| AppError::File(e.to_string()))
+    }
+
+    fn save(&self, items: &[OutboxItem]) -> Result<(), AppError> {
+        let content = serde_json::to_vec(items).map_err(|e| AppError::File(e.to_string()))?;
+        self.write_sealed(&self.dir.join("index.bin"), &content)
+    }
+
+    /// Encrypt data with the outbox key and write it privately
+    fn write_sealed(&self, path: &Path, data: &[u8]) -> Result<(), AppError> {
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| AppError::Storage("Outbox key unavailable".into()))?;
+        let sealed = crypto::encrypt_with_key(data, key)?;
+        crate::secrets::write_private(path, &sealed)
+    }
+
+    /// Read and decrypt a file written by [`Self::write_sealed`]
+    fn read_sealed(&self, path: &Path) -> Result<Vec<u8>, AppError> {
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| AppError::Storage("Outbox key unavailable".into()))?;
+        let sealed = fs::read(path).map_err(|e| AppError::File(e.to_string()))?;
+        crypto::decrypt_with_key(&sealed, key)
+    }
+
+    fn remove_payload(&self, id: u64) {
+        if let Err(e) = fs::remove_file(self.payload_path(id)) {
+            log::debug!("Failed to remove outbox payload {}: {}", id, e);
+        }
+    }
+
+    fn payload_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.bin", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Outbox in a fresh temporary directory, removed when the guard drops
+    fn outbox() -> (Outbox, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let outbox = Outbox::open(dir.path().to_path_buf(), Some(PairedKey::generate()));
+        (outbox, dir)
+    }
+
+    fn policy(collapse: bool, max_items: usize) -> OutboxConfig {
+        OutboxConfig {
+            enabled: true,
+            collapse,
+            max_items,
+        }
+    }
+
+    fn enqueue(outbox: &Outbox, data: &[u8], content_type: &str, policy: &OutboxConfig) -> u64 {
+        let fingerprint = Fingerprint::of_payload(content_type, data);
+        outbox
+            .enqueue(
+                data,
+                content_type,
+                CompressionAlgorithm::Off,
+                fingerprint,
+                policy,
+            )
+            .unwrap()
+            .id
+    }
+
+    fn content_types(outbox: &Outbox) -> Vec<String> {
+        outbox
+            .items()
+            .into_iter()
+            .map(|item| item.content_type)
+            .collect()
+    }
+
+    #[test]
+    fn collapse_keeps_the_newest_item_per_type() {
+        let (outbox, _dir) = outbox();
+        let policy = policy(true, 0);
+        enqueue(&outbox, b"first", "text", &policy);
+        enqueue(&outbox, b"png", "screenshot", &policy);
+        let newest = enqueue(&outbox, b"second", "text", &policy);
+
+        assert_eq!(content_types(&outbox), ["screenshot", "text"]);
+        let (item, data) = outbox.front().unwrap().unwrap();
+        assert_eq!(item.content_type, "screenshot");
+        assert_eq!(data, b"png");
+
+        outbox.remove(item.id).unwrap();
+        let (item, data) = outbox.front().unwrap().unwrap();
+        assert_eq!(item.id, newest);
+        assert_eq!(data, b"second");
+    }
+
+    #[test]
+    fn without_collapse_items_stay_in_order_up_to_the_limit() {
+        let (outbox, _dir) = outbox();
+        let policy = policy(false, 2);
+        enqueue(&outbox, b"first", "text", &policy);
+        enqueue(&outbox, b"second", "text", &policy);
+        enqueue(&outbox, b"third", "text", &policy);
+
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.front().unwrap().unwrap().1, b"second");
+    }
+
+    #[test]
+    fn identical_content_is_queued_once() {
+        let (outbox, _dir) = outbox();
+        let policy = policy(false, 0);
+        let first = enqueue(&outbox, b"same", "text", &policy);
+        let second = enqueue(&outbox, b"same", "text", &policy);
+
+        assert_eq!(first, second);
+        assert_eq!(outbox.len(), 1);
+    }
+
+    #[test]
+    fn queue_is_sealed_on_disk_and_survives_a_restart() {
+        let (outbox, _dir) = outbox();
+        let id = enqueue(&outbox, b"secret clipboard", "text", &policy(true, 0));
+
+        let stored = fs::read(outbox.payload_path(id)).unwrap();
+        assert!(crypto::is_encrypted(&stored));
+        assert!(!stored
+            .windows(b"secret".len())
+            .any(|window| window == b"secret"));
+
+        let reopened = Outbox::open(outbox.dir.clone(), outbox.key.clone());
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.front().unwrap().unwrap().1, b"secret clipboard");
+
+        // Another key cannot read the queue
+        assert!(Outbox::open(outbox.dir.clone(), Some(PairedKey::generate())).is_empty());
+    }
+
+    #[test]
+    fn compression_survives_a_restart() {
+        let (outbox, _dir) = outbox();
+        let fingerprint = Fingerprint::of_payload("text", b"compressed");
+        outbox
+            .enqueue(
+                b"compressed",
+                "text",
+                CompressionAlgorithm::Zstd,
+                fingerprint,
+                &policy(true, 0),
+            )
+            .unwrap();
+
+        let reopened = Outbox::open(outbox.dir.clone(), outbox.key.clone());
+        let (item, _) = reopened.front().unwrap().unwrap();
+        assert_eq!(item.compression, CompressionAlgorithm::Zstd);
+    }
+
+    #[test]
+    fn nothing_is_queued_without_a_key() {
+        let dir = TempDir::new().unwrap();
+        let outbox = Outbox::open(dir.path().to_path_buf(), None);
+        let fingerprint = Fingerprint::of_payload("text", b"data");
+        assert!(outbox
+            .enqueue(
+                b"data",
+                "text",
+                CompressionAlgorithm::Off,
+                fingerprint,
+                &policy(true, 0)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn clear_removes_payloads() {
+        let (outbox, _dir) = outbox();
+        let id = enqueue(&outbox, b"data", "text", &policy(true, 0));
+        outbox.clear().unwrap();
+
+        assert!(outbox.is_empty());
+        assert!(!outbox.payload_path(id).exists());
+    }
+}
@@ -0,0 +1,439 @@
+//! Device pairing
+//!
+//! One device generates a random encryption key and shows it as a pairing
+//! code and QR code; other devices import it. Keys are stored per server
+//! account in the secrets store, and rotated-out key ids are remembered so
+//! payloads sealed with them can be reported clearly.
+
+use crate::crypto::{Cipher, PairedKey, KEY_LEN};
+use crate::error::AppError;
+use crate::secrets;
+use data_encoding::BASE32_NOPAD;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// Prefix of a pairing code
+const CODE_PREFIX: &str = "GCOPY";
+
+/// Characters per dash-separated group in a pairing code
+const CODE_GROUP_LEN: usize = 4;
+
+/// Key entry as persisted on disk
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredKey {
+    key_id: String,
+    key: String,
+    created_at: u64,
+}
+
+impl StoredKey {
+    fn new(key: &PairedKey) -> Self {
+        Self {
+            key_id: key.id(),
+            key: BASE32_NOPAD.encode(key.bytes()),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn to_key(&self) -> Option<PairedKey> {
+        let bytes = BASE32_NOPAD.decode(self.key.as_bytes()).ok()?;
+        let key: [u8; KEY_LEN] = bytes.try_into().ok()?;
+        Some(PairedKey::from_bytes(key))
+    }
+}
+
+/// Keys of a single server account
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountKeys {
+    current: Option<StoredKey>,
+    retired: Vec<String>,
+}
+
+impl AccountKeys {
+    /// Replace the current key, retiring the previous one
+    fn replace(&mut self, key: &PairedKey) {
+        if let Some(old) = self.current.take() {
+            if old.key_id != key.id() && !self.retired.contains(&old.key_id) {
+                self.retired.push(old.key_id);
+            }
+        }
+        self.retired.retain(|id| *id != key.id());
+        self.current = Some(StoredKey::new(key));
+    }
+}
+
+/// All paired keys, keyed by account id
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyStore {
+    accounts: HashMap<String, AccountKeys>,
+}
+
+/// Secrets store entry holding all paired keys
+const KEYS_SECRET: &str = "paired-keys";
+
+impl KeyStore {
+    fn load() -> Result<Self, AppError> {
+        match secrets::get(KEYS_SECRET)? {
+            Some(content) => {
+                serde_json::from_str(&content).map_err(|e| AppError::Storage(e.to_string()))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let content = serde_json::to_string(self).map_err(|e| AppError::Storage(e.to_string()))?;
+        secrets::set(KEYS_SECRET, &content)
+    }
+}
+
+/// Paired keys loaded from the secrets store.
+///
+/// Every push and pull needs them, so the store is read once and kept until
+/// pairing changes it.
+#[derive(Default)]
+pub struct PairedKeys {
+    store: Mutex<Option<KeyStore>>,
+}
+
+impl PairedKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys of an account, loading the store on first use
+    fn account(&self, account: &str) -> Result<AccountKeys, AppError> {
+        self.with_store(|store| store.accounts.get(account).cloned().unwrap_or_default())
+    }
+
+    /// Change the keys and persist them
+    fn update<T>(&self, change: impl FnOnce(&mut KeyStore) -> T) -> Result<T, AppError> {
+        let mut cached = self.lock()?;
+        let mut store = match cached.take() {
+            Some(store) => store,
+            None => KeyStore::load()?,
+        };
+        let result = change(&mut store);
+
+        // Keep the old keys cached if they could not be saved
+        let saved = store.save();
+        if saved.is_ok() {
+            *cached = Some(store);
+        }
+        saved.map(|_| result)
+    }
+
+    fn with_store<T>(&self, read: impl FnOnce(&KeyStore) -> T) -> Result<T, AppError> {
+        let mut cached = self.lock()?;
+        let store = match cached.take() {
+            Some(store) => store,
+            None => KeyStore::load()?,
+        };
+        let result = read(&store);
+        *cached = Some(store);
+        Ok(result)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Option<KeyStore>>, AppError> {
+        self.store
+            .lock()
+            .map_err(|e| AppError::Storage(e.to_string()))
+    }
+}
+
+/// Pairing details shown to the user
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingInfo {
+    pub account: String,
+    pub key_id: String,
+    pub code: String,
+    pub qr_svg: String,
+}
+
+/// Pairing status for frontend
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingStatus {
+    pub account: String,
+    pub paired: bool,
+    pub key_id: Option<String>,
+    pub retired_key_ids: Vec<String>,
+}
+
+/// Identify a server account for key storage
+fn account_id(server_url: &str, account: Option<&str>) -> String {
+    format!(
+        "{}@{}",
+        account.unwrap_or("anonymous"),
+        server_url.trim_end_matches('/')
+    )
+}
+
+/// Account id of the current server and login
+async fn current_account_id(app: &AppHandle) -> String {
+    let state = app.state::<crate::AppState>();
+    let server_url = state.config.lock().await.server_url.clone();
+    let account = state.sync_state.account();
+    account_id(&server_url, account.as_deref())
+}
+
+/// Format a key as a pairing code
+fn encode_code(key: &PairedKey) -> String {
+    let encoded = BASE32_NOPAD.encode(key.bytes());
+    let groups: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(CODE_GROUP_LEN)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    format!("{}-{}", CODE_PREFIX, groups.join("-"))
+}
+
+/// Parse a pairing code typed or scanned by the user
+fn decode_code(code: &str) -> Result<PairedKey, AppError> {
+    let code = code.trim().to_ascii_uppercase();
+    let normalized: String = code
+        .strip_prefix(CODE_PREFIX)
+        .unwrap_or(&code)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+
+    let bytes = BASE32_NOPAD
+        .decode(normalized.as_bytes())
+        .map_err(|_| AppError::Encryption("Invalid pairing code".into()))?;
+    let key: [u8; KEY_LEN] = bytes
+        .try_into()
+        .map_err(|_| AppError::Encryption("Invalid pairing code length".into()))?;
+    Ok(PairedKey::from_bytes(key))
+}
+
+/// Build the pairing details for a key
+fn pairing_info(account: String, key: &PairedKey) -> Result<PairingInfo, AppError> {
+    let code = encode_code(key);
+    let qr_svg = QrCode::new(code.as_bytes())
+        .map_err(|e| AppError::Encryption(e.to_string()))?
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+
+    Ok(PairingInfo {
+        account,
+        key_id: key.id(),
+        code,
+        qr_svg,
+    })
+}
+
+/// Build the cipher for the current account from config and paired keys
+pub async fn current_cipher(app: &AppHandle) -> Cipher {
+    let state = app.state::<crate::AppState>();
+    let encryption = state.config.lock().await.encryption.clone();
+    let account = current_account_id(app).await;
+
+    let keys = state
+        .sync_state
+        .paired_keys
+        .account(&account)
+        .unwrap_or_else(|e| {
+            log::error!("Failed to load paired keys: {}", e);
+            AccountKeys::default()
+        });
+
+    let paired = keys.current.as_ref().and_then(StoredKey::to_key);
+    Cipher::new(&encryption, paired, keys.retired)
+}
+
+/// Tauri command: Show the pairing code, generating a key if needed
+#[tauri::command]
+pub async fn create_pairing(app: AppHandle) -> Result<PairingInfo, AppError> {
+    crate::auth::refresh_account(&app).await;
+    let account = current_account_id(&app).await;
+    let state = app.state::<crate::AppState>();
+    let paired_keys = &state.sync_state.paired_keys;
+
+    let current = paired_keys
+        .account(&account)?
+        .current
+        .as_ref()
+        .and_then(StoredKey::to_key);
+    let key = match current {
+        Some(key) => key,
+        None => {
+            let key = PairedKey::generate();
+            paired_keys.update(|store| {
+                store
+                    .accounts
+                    .entry(account.clone())
+                    .or_default()
+                    .replace(&key)
+            })?;
+            log::info!("Generated pairing key {} for {}", key.id(), account);
+            key
+        }
+    };
+
+    pairing_info(account, &key)
+}
+
+/// Tauri command: Import a pairing code shown on another device
+#[tauri::command]
+pub async fn import_pairing(app: AppHandle, code: String) -> Result<PairingInfo, AppError> {
+    let key = decode_code(&code)?;
+
+    crate::auth::refresh_account(&app).await;
+    let account = current_account_id(&app).await;
+
+    let state = app.state::<crate::AppState>();
+    state.sync_state.paired_keys.update(|store| {
+        store
+            .accounts
+            .entry(account.clone())
+            .or_default()
+            .replace(&key)
+    })?;
+
+    log::info!("Imported pairing key {} for {}", key.id(), account);
+
+    pairing_info(account, &key)
+}
+
+/// Tauri command: Rotate the key; other devices must be paired again
+#[tauri::command]
+pub async fn rotate_pairing_key(app: AppHandle) -> Result<PairingInfo, AppError> {
+    crate::auth::refresh_account(&app).await;
+    let account = current_account_id(&app).await;
+
+    let key = PairedKey::generate();
+    let state = app.state::<crate::AppState>();
+    state.sync_state.paired_keys.update(|store| {
+        store
+            .accounts
+            .entry(account.clone())
+            .or_default()
+            .replace(&key)
+    })?;
+
+    log::info!("Rotated pairing key to {} for {}", key.id(), account);
+
+    pairing_info(account, &key)
+}
+
+/// Tauri command: Forget the paired key of the current account
+#[tauri::command]
+pub async fn unpair(app: AppHandle) -> Result<(), AppError> {
+    let account = current_account_id(&app).await;
+
+    let state = app.state::<crate::AppState>();
+    let removed = state
+        .sync_state
+        .paired_keys
+        .update(|store| store.accounts.remove(&account).is_some())?;
+    if removed {
+        log::info!("Removed pairing key for {}", account);
+    }
+
+    Ok(())
+}
+
+/// Tauri command: Get pairing status of the current account
+#[tauri::command]
+pub async fn get_pairing_status(app: AppHandle) -> Result<PairingStatus, AppError> {
+    let account = current_account_id(&app).await;
+    let keys = app
+        .state::<crate::AppState>()
+        .sync_state
+        .paired_keys
+        .account(&account)?;
+
+    Ok(PairingStatus {
+        account,
+        paired: keys.current.is_some(),
+        key_id: keys.current.map(|k| k.key_id),
+        retired_key_ids: keys.retired,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairing_code_round_trip() {
+        let key = PairedKey::generate();
+        let code = encode_code(&key);
+        assert!(code.starts_with("GCOPY-"));
+        assert!(code
+            .split('-')
+            .skip(1)
+            .all(|group| group.len() <= CODE_GROUP_LEN));
+        assert_eq!(decode_code(&code).unwrap().id(), key.id());
+    }
+
+    #[test]
+    fn pairing_code_tolerates_typing() {
+        let key = PairedKey::generate();
+        let code = encode_code(&key);
+
+        let typed = format!("  {}\n", code.to_lowercase().replace('-', " "));
+        assert_eq!(decode_code(&typed).unwrap().id(), key.id());
+
+        let without_prefix = code.trim_start_matches("GCOPY-");
+        assert_eq!(decode_code(without_prefix).unwrap().id(), key.id());
+    }
+
+    #[test]
+    fn invalid_pairing_codes_are_rejected() {
+        assert!(decode_code("GCOPY-1111").is_err());
+        assert!(decode_code("GCOPY-ABCD-EFGH").is_err());
+        assert!(decode_code("").is_err());
+    }
+
+    #[test]
+    fn replacing_a_key_retires_the_old_one() {
+        let first = PairedKey::generate();
+        let second = PairedKey::generate();
+
+        let mut keys = AccountKeys::default();
+        keys.replace(&first);
+        keys.replace(&second);
+        keys.replace(&second);
+        assert_eq!(keys.current.as_ref().unwrap().key_id, second.id());
+        assert_eq!(keys.retired, vec![first.id()]);
+
+        // Importing a retired key again makes it current
+        keys.replace(&first);
+        assert_eq!(keys.retired, vec![second.id()]);
+        assert_eq!(
+            keys.current
+                .as_ref()
+                .and_then(StoredKey::to_key)
+                .unwrap()
+                .id(),
+            first.id()
+        );
+    }
+
+    #[test]
+    fn account_ids_ignore_trailing_slash() {
+        assert_eq!(
+            account_id("https://gcopy.example.com/", Some("me@example.com")),
+            account_id("https://gcopy.example.com", Some("me@example.com"))
+        );
+        assert_eq!(
+            account_id("https://gcopy.example.com", None),
+            "anonymous@https://gcopy.example.com"
+        );
+    }
+}
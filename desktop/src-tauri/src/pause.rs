@@ -0,0 +1,201 @@
+//! Timed pause of automatic sync
+//!
+//! Automatic sync can be paused for a while instead of being switched off;
+//! it resumes on its own once the pause runs out.
+
+use chrono::{DateTime, Days, Local, TimeZone};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// How long to pause
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PauseFor {
+    /// A number of minutes
+    Minutes(u64),
+    /// Until local midnight
+    UntilTomorrow,
+}
+
+impl PauseFor {
+    /// Unix time in seconds the pause ends, with midnight taken in the
+    /// time zone of `now`
+    fn until<Tz: TimeZone>(self, now: &DateTime<Tz>) -> u64 {
+        let secs = now.timestamp().max(0) as u64;
+        match self {
+            Self::Minutes(minutes) => secs.saturating_add(minutes.saturating_mul(60)),
+            Self::UntilTomorrow => (now.date_naive() + Days::new(1))
+                .and_hms_opt(0, 0, 0)
+                .and_then(|midnight| midnight.and_local_timezone(now.timezone()).earliest())
+                .map(|midnight| midnight.timestamp().max(0) as u64)
+                .unwrap_or(secs + 24 * 60 * 60),
+        }
+    }
+}
+
+/// Pause deadline shared by the sync engine, tray and commands
+#[derive(Default)]
+pub struct Pause {
+    /// Unix time in seconds the pause ends, 0 when not paused
+    until: AtomicU64,
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start or extend a pause; returns the time left
+    pub fn pause(&self, duration: PauseFor) -> Duration {
+        let now = Local::now();
+        let until = duration.until(&now);
+        let now = now.timestamp().max(0) as u64;
+        self.until.store(until, Ordering::SeqCst);
+        Duration::from_secs(until.saturating_sub(now))
+    }
+
+    /// End the pause early
+    pub fn resume(&self) {
+        self.until.store(0, Ordering::SeqCst);
+    }
+
+    /// Time left, if paused
+    pub fn remaining(&self) -> Option<Duration> {
+        let until = self.until.load(Ordering::SeqCst);
+        let now = now_secs();
+        (until > now).then(|| Duration::from_secs(until - now))
+    }
+
+    /// Whether the pause is still running
+    pub fn is_paused(&self) -> bool {
+        self.remaining().is_some()
+    }
+
+    /// Clear a pause that ran out; returns whether one did
+    pub fn take_expired(&self) -> bool {
+        let until = self.until.load(Ordering::SeqCst);
+        until != 0
+            && until <= now_secs()
+            && self
+                .until
+                .compare_exchange(until, 0, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Short human readable duration, e.g. `1h 05m`
+pub fn format_remaining(remaining: Duration) -> String {
+    let minutes = remaining.as_secs().div_ceil(60);
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Tauri command: Pause automatic sync; returns the seconds until it resumes
+#[tauri::command]
+pub fn pause_auto_sync(app: AppHandle, duration: PauseFor) -> u64 {
+    let state = app.state::<crate::AppState>();
+    let remaining = state.sync_state.pause.pause(duration);
+    log::info!("Auto sync paused for {}", format_remaining(remaining));
+    crate::sync::auto_sync_changed(&app);
+    remaining.as_secs()
+}
+
+/// Tauri command: Resume automatic sync before the pause runs out
+#[tauri::command]
+pub fn resume_auto_sync(app: AppHandle) {
+    let state = app.state::<crate::AppState>();
+    state.sync_state.pause.resume();
+    state
+        .sync_state
+        .auto_sync_enabled
+        .store(true, Ordering::SeqCst);
+    log::info!("Auto sync resumed");
+    crate::sync::auto_sync_changed(&app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn minutes_are_added_to_now() {
+        let now = Local::now();
+        let secs = now.timestamp() as u64;
+        assert_eq!(PauseFor::Minutes(15).until(&now), secs + 15 * 60);
+        assert_eq!(PauseFor::Minutes(0).until(&now), secs);
+    }
+
+    #[test]
+    fn tomorrow_is_the_next_local_midnight() {
+        let tz = FixedOffset::east_opt(8 * 60 * 60).unwrap();
+        let midnight = tz
+            .with_ymd_and_hms(2024, 3, 2, 0, 0, 0)
+            .unwrap()
+            .timestamp() as u64;
+
+        let evening = tz.with_ymd_and_hms(2024, 3, 1, 23, 59, 59).unwrap();
+        assert_eq!(PauseFor::UntilTomorrow.until(&evening), midnight);
+
+        let morning = tz.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(PauseFor::UntilTomorrow.until(&morning), midnight);
+
+        // Right at midnight the pause lasts the whole new day
+        let at_midnight = tz.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap();
+        assert_eq!(
+            PauseFor::UntilTomorrow.until(&at_midnight),
+            midnight + 24 * 60 * 60
+        );
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let pause = Pause::new();
+        assert!(!pause.is_paused());
+
+        let left = pause.pause(PauseFor::Minutes(60));
+        assert_eq!(left, Duration::from_secs(60 * 60));
+        assert!(pause.is_paused());
+        assert!(pause.remaining().unwrap() <= left);
+        assert!(!pause.take_expired());
+
+        pause.resume();
+        assert!(!pause.is_paused());
+        assert!(!pause.take_expired());
+    }
+
+    #[test]
+    fn expired_pause_is_taken_once() {
+        let pause = Pause::new();
+        pause.until.store(now_secs() - 1, Ordering::SeqCst);
+
+        assert!(!pause.is_paused());
+        assert!(pause.take_expired());
+        assert!(!pause.take_expired());
+        assert_eq!(pause.until.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn formats_remaining_time() {
+        assert_eq!(format_remaining(Duration::from_secs(1)), "1m");
+        assert_eq!(format_remaining(Duration::from_secs(15 * 60)), "15m");
+        assert_eq!(format_remaining(Duration::from_secs(59 * 60 + 1)), "1h 00m");
+        assert_eq!(format_remaining(Duration::from_secs(65 * 60)), "1h 05m");
+        assert_eq!(
+            format_remaining(Duration::from_secs(25 * 60 * 60)),
+            "25h 00m"
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! Certificate pinning
+//!
+//! In trust-on-first-use mode server certificates are not checked against CA
+//! roots. The fingerprint seen on the first connection to a host is pinned
+//! once a request over it succeeds, and later connections presenting another
+//! certificate are refused. This lets self-hosted servers use self-signed
+//! certificates without turning verification off.
+
+use crate::error::AppError;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// Pinned certificate of a host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificatePin {
+    pub host: String,
+    /// SHA-256 fingerprint of the certificate, hex encoded
+    pub sha256: String,
+    /// Unix time in milliseconds the pin was recorded
+    pub pinned_at: u64,
+}
+
+/// Certificate refused because it does not match the pin of its host
+#[derive(Debug, Clone)]
+struct PinMismatch {
+    expected: String,
+    actual: String,
+}
+
+/// Pinned certificates, persisted in the config directory
+#[derive(Debug)]
+pub struct PinStore {
+    path: PathBuf,
+    pins: Mutex<Vec<CertificatePin>>,
+    /// Certificates of unpinned hosts, pinned once a request succeeds
+    pending: Mutex<HashMap<String, String>>,
+    /// Refused certificates by host, until they are reported
+    mismatches: Mutex<HashMap<String, PinMismatch>>,
+}
+
+impl PinStore {
+    /// Load the pins from file
+    pub fn load() -> Self {
+        Self::load_from(Self::pins_path())
+    }
+
+    fn load_from(path: PathBuf) -> Self {
+        let pins = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            pins: Mutex::new(pins),
+            pending: Mutex::new(HashMap::new()),
+            mismatches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// All pinned certificates
+    pub fn pins(&self) -> Vec<CertificatePin> {
+        self.pins
+            .lock()
+            .map(|pins| pins.clone())
+            .unwrap_or_default()
+    }
+
+    /// Forget the pin of one host, or all pins; returns how many were removed
+    pub fn reset(&self, host: Option<&str>) -> Result<usize, AppError> {
+        let mut pins = self
+            .pins
+            .lock()
+            .map_err(|e| AppError::Config(e.to_string()))?;
+        let before = pins.len();
+        pins.retain(|pin| host.is_some_and(|host| host != pin.host));
+        self.save(&pins)?;
+        Ok(before - pins.len())
+    }
+
+    /// Whether a certificate is acceptable for `host`. Unpinned hosts are
+    /// accepted, and their certificate is held until [`Self::confirm`].
+    fn check(&self, host: &str, sha256: String) -> bool {
+        let Ok(pins) = self.pins.lock() else {
+            return false;
+        };
+
+        let Some(pin) = pins.iter().find(|pin| pin.host == host) else {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.insert(host.to_string(), sha256);
+            }
+            return true;
+        };
+        if pin.sha256 == sha256 {
+            return true;
+        }
+
+        log::error!("Certificate of {} does not match its pin", host);
+        if let Ok(mut mismatches) = self.mismatches.lock() {
+            mismatches.insert(
+                host.to_string(),
+                PinMismatch {
+                    expected: pin.sha256.clone(),
+                    actual: sha256,
+                },
+            );
+        }
+        false
+    }
+
+    /// Pin the certificate a request to `url` just succeeded with, if its
+    /// host is not pinned yet
+    pub(crate) fn confirm(&self, url: &str) {
+        let Some(host) = host_of(url) else {
+            return;
+        };
+        let Some(sha256) = self.pending.lock().ok().and_then(|mut p| p.remove(&host)) else {
+            return;
+        };
+        let Ok(mut pins) = self.pins.lock() else {
+            return;
+        };
+        if pins.iter().any(|pin| pin.host == host) {
+            return;
+        }
+
+        log::info!("Pinning certificate of {}: {}", host, sha256);
+        pins.push(CertificatePin {
+            host,
+            sha256,
+            pinned_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        });
+        if let Err(e) = self.save(&pins) {
+            log::error!("Failed to save certificate pins: {}", e);
+        }
+    }
+
+    /// Replace a network error of a request to `url` caused by a refused
+    /// certificate with a specific error
+    pub(crate) fn explain(&self, url: &str, error: AppError) -> AppError {
+        if !matches!(error, AppError::Network(_)) {
+            return error;
+        }
+        let Some(host) = host_of(url) else {
+            return error;
+        };
+
+        match self
+            .mismatches
+            .lock()
+            .ok()
+            .and_then(|mut m| m.remove(&host))
+        {
+            Some(mismatch) => AppError::CertificateMismatch {
+                host,
+                expected: mismatch.expected,
+                actual: mismatch.actual,
+            },
+            None => error,
+        }
+    }
+
+    fn save(&self, pins: &[CertificatePin]) -> Result<(), AppError> {
+        let path = &self.path;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::Config(e.to_string()))?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(pins).map_err(|e| AppError::Config(e.to_string()))?;
+        fs::write(path, content).map_err(|e| AppError::Config(e.to_string()))
+    }
+
+    /// Get pins file path
+    fn pins_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("gcopy")
+            .join("pins.json")
+    }
+}
+
+/// Host of a server URL as TLS sees it, without IPv6 brackets
+fn host_of(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    Some(
+        host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+    )
+}
+
+/// Accepts the pinned certificate of a host instead of CA-issued ones
+#[derive(Debug)]
+struct PinVerifier {
+    pins: Arc<PinStore>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let sha256 = hex::encode(Sha256::digest(end_entity.as_ref()));
+        if self.pins.check(&server_name.to_str(), sha256) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "certificate does not match the pinned certificate".into(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// TLS settings verifying servers by their pins, with an optional client
+/// certificate for mutual TLS
+pub(crate) fn tls_config(
+    pins: Arc<PinStore>,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+) -> Result<ClientConfig, AppError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| AppError::Config(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinVerifier { pins, provider }));
+
+    match client_auth {
+        Some((certificates, key)) => builder
+            .with_client_auth_cert(certificates, key)
+            .map_err(|e| AppError::Config(format!("Invalid client certificate: {}", e))),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Tauri command: List pinned server certificates
+#[tauri::command]
+pub fn get_certificate_pins(app: AppHandle) -> Vec<CertificatePin> {
+    let state = app.state::<crate::AppState>();
+    state.sync_state.pins.pins()
+}
+
+/// Tauri command: Forget the pin of a host, or all pins without a host
+#[tauri::command]
+pub async fn reset_certificate_pins(
+    app: AppHandle,
+    host: Option<String>,
+) -> Result<usize, AppError> {
+    let state = app.state::<crate::AppState>();
+    let removed = state.sync_state.pins.reset(host.as_deref())?;
+
+    // Drop pooled connections so the next one is pinned again
+    let config = state.config.lock().await.clone();
+    state.sync_state.rebuild_client(&config)?;
+
+    log::info!("Reset {} certificate pin(s)", removed);
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const URL: &str = "https://clip.example.com:8443";
+
+    fn store() -> (PinStore, TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        (PinStore::load_from(dir.path().join("pins.json")), dir)
+    }
+
+    fn hosts(store: &PinStore) -> Vec<String> {
+        store.pins().into_iter().map(|pin| pin.host).collect()
+    }
+
+    #[test]
+    fn pins_on_first_use_once_confirmed() {
+        let (store, dir) = store();
+
+        assert!(store.check("clip.example.com", "aa".into()));
+        assert!(store.pins().is_empty());
+        assert!(PinStore::load_from(dir.path().join("pins.json"))
+            .pins()
+            .is_empty());
+
+        store.confirm(URL);
+        let pins = PinStore::load_from(dir.path().join("pins.json")).pins();
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].host, "clip.example.com");
+        assert_eq!(pins[0].sha256, "aa");
+    }
+
+    #[test]
+    fn unconfirmed_certificates_are_not_pinned() {
+        let (store, _dir) = store();
+
+        assert!(store.check("clip.example.com", "aa".into()));
+        store.confirm("https://other.example.com");
+        assert!(store.pins().is_empty());
+
+        // The certificate seen last is the one pinned
+        assert!(store.check("clip.example.com", "bb".into()));
+        store.confirm(URL);
+        assert_eq!(store.pins()[0].sha256, "bb");
+    }
+
+    #[test]
+    fn refuses_other_certificates_of_pinned_hosts() {
+        let (store, _dir) = store();
+        store.check("clip.example.com", "aa".into());
+        store.confirm(URL);
+
+        assert!(store.check("clip.example.com", "aa".into()));
+        assert!(!store.check("clip.example.com", "bb".into()));
+        assert!(store.check("other.example.com", "bb".into()));
+    }
+
+    #[test]
+    fn explains_mismatches_of_the_requested_host_only() {
+        let (store, _dir) = store();
+        store.check("clip.example.com", "aa".into());
+        store.confirm(URL);
+        store.check("clip.example.com", "bb".into());
+
+        let error = store.explain("https://other.example.com", AppError::Network("x".into()));
+        assert!(matches!(error, AppError::Network(_)));
+        let error = store.explain(URL, AppError::Unauthorized);
+        assert!(matches!(error, AppError::Unauthorized));
+
+        match store.explain(URL, AppError::Network("x".into())) {
+            AppError::CertificateMismatch {
+                host,
+                expected,
+                actual,
+            } => {
+                assert_eq!(host, "clip.example.com");
+                assert_eq!(expected, "aa");
+                assert_eq!(actual, "bb");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        // Reported once
+        let error = store.explain(URL, AppError::Network("x".into()));
+        assert!(matches!(error, AppError::Network(_)));
+    }
+
+    #[test]
+    fn matches_ip_hosts() {
+        let (store, _dir) = store();
+        store.check("::1", "aa".into());
+        store.confirm("https://[::1]:8443");
+        assert_eq!(hosts(&store), ["::1"]);
+    }
+
+    #[test]
+    fn resets_one_host_or_all() {
+        let (store, dir) = store();
+        for host in ["a.example.com", "b.example.com", "c.example.com"] {
+            store.check(host, "aa".into());
+            store.confirm(&format!("https://{}", host));
+        }
+
+        assert_eq!(store.reset(Some("b.example.com")).unwrap(), 1);
+        assert_eq!(hosts(&store), ["a.example.com", "c.example.com"]);
+        assert_eq!(store.reset(Some("b.example.com")).unwrap(), 0);
+
+        let reloaded = PinStore::load_from(dir.path().join("pins.json"));
+        assert_eq!(hosts(&reloaded), ["a.example.com", "c.example.com"]);
+
+        assert_eq!(store.reset(None).unwrap(), 2);
+        assert!(PinStore::load_from(dir.path().join("pins.json"))
+            .pins()
+            .is_empty());
+
+        // A reset host is pinned again on its next connection
+        assert!(store.check("a.example.com", "bb".into()));
+    }
+
+    #[test]
+    fn ignores_a_corrupted_pins_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pins.json");
+        fs::write(&path, "not json").unwrap();
+        assert!(PinStore::load_from(path).pins().is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! Recent items
+//!
+//! Keeps the last items copied locally or pulled from the server, so one can
+//! be copied again from the tray. Items are kept in memory only and are gone
+//! after a restart.
+
+use crate::clipboard::{write_clipboard, ClipboardContent};
+use crate::error::AppError;
+use crate::fingerprint::Fingerprint;
+use serde::Serialize;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// Characters of text shown in a menu label
+const LABEL_LEN: usize = 40;
+
+/// Item copied or pulled a moment ago
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentItem {
+    pub id: u64,
+    /// Menu label: beginning of the text or image size
+    pub label: String,
+    /// Device the item was pulled from, if any
+    pub device: Option<String>,
+    /// Unix time in milliseconds
+    pub at: u64,
+    #[serde(skip)]
+    fingerprint: Fingerprint,
+    #[serde(skip)]
+    pub(crate) content: ClipboardContent,
+}
+
+/// Most recent items, newest first
+#[derive(Default)]
+pub struct RecentItems {
+    next_id: AtomicU64,
+    items: Mutex<Vec<RecentItem>>,
+}
+
+impl RecentItems {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Items, newest first
+    pub fn items(&self) -> Vec<RecentItem> {
+        self.items
+            .lock()
+            .map(|items| items.clone())
+            .unwrap_or_default()
+    }
+
+    /// Item by id
+    pub fn get(&self, id: u64) -> Option<RecentItem> {
+        self.items
+            .lock()
+            .ok()?
+            .iter()
+            .find(|item| item.id == id)
+            .cloned()
+    }
+
+    /// Add an item, keeping at most `max_items`; an item already in the list
+    /// moves to the top
+    pub fn record(&self, content: ClipboardContent, device: Option<String>, max_items: usize) {
+        let fingerprint = Fingerprint::of(&content);
+        let Ok(mut items) = self.items.lock() else {
+            return;
+        };
+
+        items.retain(|item| item.fingerprint != fingerprint);
+        if max_items == 0 {
+            items.clear();
+            return;
+        }
+
+        items.insert(
+            0,
+            RecentItem {
+                id: self.next_id.fetch_add(1, Ordering::SeqCst) + 1,
+                label: label(&content),
+                device,
+                at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or_default(),
+                fingerprint,
+                content,
+            },
+        );
+        items.truncate(max_items);
+    }
+}
+
+/// Short description of an item for the tray menu
+fn label(content: &ClipboardContent) -> String {
+    match content {
+        ClipboardContent::Text(text) => {
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let mut label: String = text.chars().take(LABEL_LEN).collect();
+            if label.len() < text.len() {
+                label.push('…');
+            }
+            label
+        }
+        ClipboardContent::Image(data) => match png::Decoder::new(Cursor::new(data)).read_info() {
+            Ok(reader) => format!(
+                "截图 Screenshot {}×{}",
+                reader.info().width,
+                reader.info().height
+            ),
+            Err(_) => "截图 Screenshot".to_string(),
+        },
+    }
+}
+
+/// Remember an item and show it in the tray
+pub(crate) fn record(app: &AppHandle, content: ClipboardContent, device: Option<String>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<crate::AppState>();
+        let max_items = state.config.lock().await.recent.max_items;
+        state.sync_state.recent.record(content, device, max_items);
+        crate::tray::refresh_recent(&app);
+    });
+}
+
+/// Copy a recent item to the clipboard again, and push it when configured
+pub(crate) async fn restore(app: &AppHandle, id: u64) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+    let item = state
+        .sync_state
+        .recent
+        .get(id)
+        .ok_or_else(|| AppError::Clipboard("Recent item no longer available".into()))?;
+    let repush = state.config.lock().await.recent.repush;
+
+    let content = item.content;
+    write_clipboard(content.clone())?;
+    record(app, content.clone(), item.device);
+    log::info!("Copied recent item {} again", id);
+    if repush {
+        let pushed = crate::sync::push_to_server(app, content).await;
+        crate::sync::track(app, pushed).await?;
+    }
+
+    Ok(())
+}
+
+/// Tauri command: List recent items, newest first
+#[tauri::command]
+pub fn get_recent_items(app: AppHandle) -> Vec<RecentItem> {
+    let state = app.state::<crate::AppState>();
+    state.sync_state.recent.items()
+}
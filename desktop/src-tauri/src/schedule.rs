@@ -0,0 +1,108 @@
+//! Adaptive poll scheduling
+//!
+//! Polls at `sync_interval` right after local or remote activity and backs
+//! off gradually while idle, up to a configurable ceiling.
+
+use crate::config::AppConfig;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Interval until the next poll, grown on every idle poll
+#[derive(Default)]
+pub struct PollSchedule {
+    /// Interval for the next poll, `None` right after activity
+    current: Mutex<Option<Duration>>,
+}
+
+impl PollSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Go back to the shortest interval
+    pub fn activity(&self) {
+        if let Ok(mut current) = self.current.lock() {
+            *current = None;
+        }
+    }
+
+    /// Interval to wait before the next poll
+    pub fn next(&self, config: &AppConfig) -> Duration {
+        let base = Duration::from_secs(config.sync_interval.max(1));
+        if !config.polling.adaptive {
+            return base;
+        }
+
+        let ceiling = Duration::from_secs(config.polling.max_interval_secs).max(base);
+        let Ok(mut current) = self.current.lock() else {
+            return base;
+        };
+
+        let interval = current.unwrap_or(base).clamp(base, ceiling);
+        let factor = config.polling.backoff_factor.max(1.0);
+        *current = Some(interval.mul_f64(factor).min(ceiling));
+        interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PollingConfig;
+
+    fn config(adaptive: bool) -> AppConfig {
+        AppConfig {
+            sync_interval: 2,
+            polling: PollingConfig {
+                adaptive,
+                max_interval_secs: 20,
+                backoff_factor: 2.0,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn secs(schedule: &PollSchedule, config: &AppConfig, polls: usize) -> Vec<u64> {
+        (0..polls)
+            .map(|_| schedule.next(config).as_secs())
+            .collect()
+    }
+
+    #[test]
+    fn backs_off_gradually_up_to_the_ceiling() {
+        let schedule = PollSchedule::new();
+        assert_eq!(secs(&schedule, &config(true), 7), [2, 4, 8, 16, 20, 20, 20]);
+    }
+
+    #[test]
+    fn activity_returns_to_the_shortest_interval() {
+        let schedule = PollSchedule::new();
+        let config = config(true);
+        secs(&schedule, &config, 5);
+
+        schedule.activity();
+        assert_eq!(secs(&schedule, &config, 3), [2, 4, 8]);
+    }
+
+    #[test]
+    fn fixed_interval_without_adaptive_polling() {
+        let schedule = PollSchedule::new();
+        assert_eq!(secs(&schedule, &config(false), 3), [2, 2, 2]);
+    }
+
+    #[test]
+    fn ceiling_below_the_base_interval_keeps_the_base() {
+        let schedule = PollSchedule::new();
+        let mut config = config(true);
+        config.sync_interval = 30;
+        assert_eq!(secs(&schedule, &config, 3), [30, 30, 30]);
+    }
+
+    #[test]
+    fn factors_below_one_do_not_shrink_the_interval() {
+        let schedule = PollSchedule::new();
+        let mut config = config(true);
+        config.polling.backoff_factor = 0.5;
+        assert_eq!(secs(&schedule, &config, 3), [2, 2, 2]);
+    }
+}
@@ -0,0 +1,281 @@
+//! Secure storage of secrets at rest
+//!
+//! Secrets such as the session cookie, the encryption passphrase and paired
+//! keys are kept in the OS keyring (Secret Service on Linux, Keychain on
+//! macOS, Credential Manager on Windows). When no keyring is available they
+//! fall back to a file encrypted with a local key, both readable by the
+//! current user only.
+
+use crate::crypto::{self, PairedKey, KEY_LEN};
+use crate::error::AppError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Service name under which keyring entries are stored
+const SERVICE: &str = "cn.llaoj.gcopy";
+
+/// Serializes access to the fallback file
+static FILE_MUTEX: Mutex<()> = Mutex::new(());
+
+/// Where secrets end up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Keyring,
+    EncryptedFile,
+}
+
+/// Pick the backend once per process
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+    *BACKEND.get_or_init(|| {
+        let probe =
+            keyring::Entry::new(SERVICE, "probe").and_then(|entry| match entry.get_password() {
+                Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(e),
+            });
+
+        match probe {
+            Ok(()) => {
+                log::info!("Storing secrets in the OS keyring");
+                Backend::Keyring
+            }
+            Err(e) => {
+                log::warn!(
+                    "OS keyring unavailable ({}), storing secrets in an encrypted file",
+                    e
+                );
+                Backend::EncryptedFile
+            }
+        }
+    })
+}
+
+/// Read a secret
+pub fn get(name: &str) -> Result<Option<String>, AppError> {
+    match backend() {
+        Backend::Keyring => {
+            let entry =
+                keyring::Entry::new(SERVICE, name).map_err(|e| AppError::Storage(e.to_string()))?;
+            match entry.get_password() {
+                Ok(value) => Ok(Some(value)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(AppError::Storage(e.to_string())),
+            }
+        }
+        Backend::EncryptedFile => {
+            let _lock = FILE_MUTEX
+                .lock()
+                .map_err(|e| AppError::Storage(e.to_string()))?;
+            Ok(read_file(&secrets_dir())?.remove(name))
+        }
+    }
+}
+
+/// Store a secret, replacing any previous value
+pub fn set(name: &str, value: &str) -> Result<(), AppError> {
+    match backend() {
+        Backend::Keyring => keyring::Entry::new(SERVICE, name)
+            .and_then(|entry| entry.set_password(value))
+            .map_err(|e| AppError::Storage(e.to_string())),
+        Backend::EncryptedFile => {
+            let _lock = FILE_MUTEX
+                .lock()
+                .map_err(|e| AppError::Storage(e.to_string()))?;
+            let dir = secrets_dir();
+            let mut secrets = read_file(&dir)?;
+            secrets.insert(name.to_string(), value.to_string());
+            write_file(&dir, &secrets)
+        }
+    }
+}
+
+/// Remove a secret; removing a missing secret is not an error
+pub fn delete(name: &str) -> Result<(), AppError> {
+    match backend() {
+        Backend::Keyring => {
+            let entry =
+                keyring::Entry::new(SERVICE, name).map_err(|e| AppError::Storage(e.to_string()))?;
+            match entry.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(AppError::Storage(e.to_string())),
+            }
+        }
+        Backend::EncryptedFile => {
+            let _lock = FILE_MUTEX
+                .lock()
+                .map_err(|e| AppError::Storage(e.to_string()))?;
+            let dir = secrets_dir();
+            let mut secrets = read_file(&dir)?;
+            if secrets.remove(name).is_some() {
+                write_file(&dir, &secrets)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Directory holding the fallback files
+fn secrets_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("gcopy")
+}
+
+/// Load the local file key, creating it on first use
+pub(crate) fn file_key() -> Result<PairedKey, AppError> {
+    file_key_in(&secrets_dir())
+}
+
+/// Load the file key kept in a directory, creating it on first use
+fn file_key_in(dir: &Path) -> Result<PairedKey, AppError> {
+    let path = dir.join("secrets.key");
+
+    if path.exists() {
+        let bytes = fs::read(&path).map_err(|e| AppError::Storage(e.to_string()))?;
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| AppError::Storage("Secrets key file is corrupted".into()))?;
+        return Ok(PairedKey::from_bytes(key));
+    }
+
+    let key = PairedKey::generate();
+    write_private(&path, key.bytes())?;
+    Ok(key)
+}
+
+/// Decrypt the fallback file in a directory
+fn read_file(dir: &Path) -> Result<BTreeMap<String, String>, AppError> {
+    let path = dir.join("secrets.bin");
+
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let sealed = fs::read(&path).map_err(|e| AppError::Storage(e.to_string()))?;
+    let content = crypto::decrypt_with_key(&sealed, &file_key_in(dir)?)?;
+    serde_json::from_slice(&content).map_err(|e| AppError::Storage(e.to_string()))
+}
+
+/// Encrypt and write the fallback file in a directory
+fn write_file(dir: &Path, secrets: &BTreeMap<String, String>) -> Result<(), AppError> {
+    let content = serde_json::to_vec(secrets).map_err(|e| AppError::Storage(e.to_string()))?;
+    let sealed = crypto::encrypt_with_key(&content, &file_key_in(dir)?)?;
+    write_private(&dir.join("secrets.bin"), &sealed)
+}
+
+/// Write a file readable by the current user only
+pub(crate) fn write_private(path: &Path, data: &[u8]) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::Storage(e.to_string()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(parent, fs::Permissions::from_mode(0o700))
+                .map_err(|e| AppError::Storage(e.to_string()))?;
+        }
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .map_err(|e| AppError::Storage(e.to_string()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(|e| AppError::Storage(e.to_string()))?;
+    }
+    file.write_all(data)
+        .map_err(|e| AppError::Storage(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn secrets(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let dir = TempDir::new().unwrap();
+        assert!(read_file(dir.path()).unwrap().is_empty());
+
+        let stored = secrets(&[("session:https://example.com", "user_session=abc")]);
+        write_file(dir.path(), &stored).unwrap();
+        assert_eq!(read_file(dir.path()).unwrap(), stored);
+
+        let sealed = fs::read(dir.path().join("secrets.bin")).unwrap();
+        assert!(!sealed
+            .windows(b"user_session".len())
+            .any(|window| window == b"user_session"));
+    }
+
+    #[test]
+    fn file_key_is_created_once() {
+        let dir = TempDir::new().unwrap();
+        let key = file_key_in(dir.path()).unwrap();
+        assert_eq!(file_key_in(dir.path()).unwrap().id(), key.id());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = TempDir::new().unwrap();
+        let dir = root.path().join("gcopy");
+        write_file(&dir, &secrets(&[("name", "value")])).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&dir.join("secrets.key")), 0o600);
+        assert_eq!(mode(&dir.join("secrets.bin")), 0o600);
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), &secrets(&[("name", "value")])).unwrap();
+
+        write_private(
+            &dir.path().join("secrets.key"),
+            PairedKey::generate().bytes(),
+        )
+        .unwrap();
+        assert!(read_file(dir.path()).is_err());
+    }
+
+    #[test]
+    fn missing_key_leaves_the_file_unreadable() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), &secrets(&[("name", "value")])).unwrap();
+
+        // A new key is created, but the old file stays unreadable
+        fs::remove_file(dir.path().join("secrets.key")).unwrap();
+        assert!(read_file(dir.path()).is_err());
+    }
+
+    #[test]
+    fn corrupted_key_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("secrets.key"), b"short").unwrap();
+        assert!(matches!(file_key_in(dir.path()), Err(AppError::Storage(_))));
+    }
+}
@@ -0,0 +1,37 @@
+//! Global keyboard shortcuts
+//!
+//! Registers the manual sync shortcut from the configuration. It wakes the
+//! sync engine for an immediate poll, like focusing the window does; it does
+//! not push the clipboard.
+
+use crate::config::Shortcuts;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/// Register the configured shortcuts, replacing previous ones
+pub fn register_shortcuts(app: &AppHandle, shortcuts: &Shortcuts) {
+    let global = app.global_shortcut();
+    if let Err(e) = global.unregister_all() {
+        log::warn!("Failed to unregister shortcuts: {}", e);
+    }
+
+    if shortcuts.manual_sync.is_empty() {
+        return;
+    }
+
+    let registered = global.on_shortcut(shortcuts.manual_sync.as_str(), |app, _, event| {
+        if event.state == ShortcutState::Pressed {
+            let state = app.state::<crate::AppState>();
+            state.sync_state.schedule.activity();
+            state.sync_state.connection.retry_now();
+            state.sync_state.push_channel.wake();
+        }
+    });
+    if let Err(e) = registered {
+        log::warn!(
+            "Failed to register shortcut {}: {}",
+            shortcuts.manual_sync,
+            e
+        );
+    }
+}
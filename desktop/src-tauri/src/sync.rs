@@ -3,11 +3,29 @@
 //! Handles automatic clipboard synchronization with the server.
 
 use crate::clipboard::{read_clipboard, write_clipboard, ClipboardContent};
-use crate::config::AppConfig;
+use crate::config::{AppConfig, CompressionAlgorithm};
+use crate::conflict::{ConflictChoice, PendingConflict};
+use crate::connection::{Connection, ConnectionState};
+use crate::crypto;
+use crate::cursor::{SyncCursor, SyncedItem};
+use crate::error::AppError;
+use crate::events::{ChannelState, PushChannel};
+use crate::fingerprint::Fingerprint;
+use crate::limits::ServerLimits;
+use crate::outbox::{Outbox, OutboxItem};
+use crate::pairing::PairedKeys;
+use crate::pause::Pause;
+use crate::pinning::PinStore;
+use crate::recent::RecentItems;
+use crate::schedule::PollSchedule;
+use crate::transfer::{self, TransferDirection, Transfers};
+use bytes::Bytes;
+use reqwest::cookie::Jar;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter, Manager};
 
 /// Sync state management
@@ -15,29 +33,342 @@ pub struct SyncState {
     pub auto_sync_enabled: AtomicBool,
     pub last_server_index: AtomicU64,
     pub is_syncing: AtomicBool,
-    pub server_url: String,
-    client: Client,
+    pub pause: Pause,
+    server_url: RwLock<String>,
+    pub connection: Connection,
+    pub outbox: Outbox,
+    pub push_channel: PushChannel,
+    pub schedule: PollSchedule,
+    pub transfers: Transfers,
+    pub limits: ServerLimits,
+    pub recent: RecentItems,
+    pub pins: Arc<PinStore>,
+    pub(crate) paired_keys: PairedKeys,
+    account: Mutex<Option<String>>,
+    /// Fingerprint of the local clipboard when it was last in sync with the
+    /// server
+    synced_fingerprint: Mutex<Option<Fingerprint>>,
+    last_pushed: Mutex<Option<SyncedItem>>,
+    last_pulled: Mutex<Option<SyncedItem>>,
+    /// Last pull and push, shown in the tray
+    last_pull_activity: Mutex<Option<SyncActivity>>,
+    last_push_activity: Mutex<Option<SyncActivity>>,
+    /// Conflict waiting for the user
+    pub(crate) conflict: Mutex<Option<PendingConflict>>,
+    pub(crate) cookies: Arc<Jar>,
+    client: RwLock<Client>,
 }
 
 impl SyncState {
-    pub fn new(server_url: String) -> Self {
+    pub fn new(config: &AppConfig) -> Self {
+        let server_url = config.server_url.clone();
+        let cookies = Arc::new(Jar::default());
+        crate::auth::restore_session(&cookies, &server_url);
+
+        // Fall back to a plain client so a bad setting can still be fixed
+        let pins = Arc::new(PinStore::load());
+        let client = crate::network::build_client(
+            &config.network,
+            config.access_for(&server_url),
+            &crate::device::user_agent(&config.device_name()),
+            cookies.clone(),
+            pins.clone(),
+        )
+        .unwrap_or_else(|e| {
+            log::error!("Failed to apply network settings: {}", e);
+            Client::builder()
+                .cookie_provider(cookies.clone())
+                .build()
+                .unwrap_or_default()
+        });
+
+        // Resume from the persisted cursor so a restart does not re-pull
+        // the current server item over a newer local clipboard
+        let cursor = SyncCursor::load().unwrap_or_else(|e| {
+            log::error!("Failed to load sync cursor: {}", e);
+            SyncCursor::default()
+        });
+        let cursor = if cursor.matches_server(&server_url) {
+            crate::clipboard::set_last_fingerprint(cursor.last_local_fingerprint);
+            cursor
+        } else {
+            SyncCursor::default()
+        };
+
         Self {
             auto_sync_enabled: AtomicBool::new(true),
-            last_server_index: AtomicU64::new(0),
+            last_server_index: AtomicU64::new(cursor.last_index),
             is_syncing: AtomicBool::new(false),
-            server_url,
-            client: Client::new(),
+            pause: Pause::new(),
+            server_url: RwLock::new(server_url),
+            connection: Connection::new(),
+            outbox: Outbox::load(),
+            push_channel: PushChannel::new(),
+            schedule: PollSchedule::new(),
+            transfers: Transfers::new(),
+            limits: ServerLimits::new(),
+            recent: RecentItems::new(),
+            pins,
+            paired_keys: PairedKeys::new(),
+            account: Mutex::new(None),
+            synced_fingerprint: Mutex::new(cursor.last_local_fingerprint),
+            last_pushed: Mutex::new(cursor.last_pushed),
+            last_pulled: Mutex::new(cursor.last_pulled),
+            last_pull_activity: Mutex::new(None),
+            last_push_activity: Mutex::new(None),
+            conflict: Mutex::new(None),
+            client: RwLock::new(client),
+            cookies,
+        }
+    }
+
+    /// Client for server requests
+    pub(crate) fn client(&self) -> Client {
+        self.client
+            .read()
+            .map(|client| client.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the client after the network or server settings changed
+    pub(crate) fn rebuild_client(&self, config: &AppConfig) -> Result<(), AppError> {
+        let client = crate::network::build_client(
+            &config.network,
+            config.access_for(&config.server_url),
+            &crate::device::user_agent(&config.device_name()),
+            self.cookies.clone(),
+            self.pins.clone(),
+        )?;
+        if let Ok(mut current) = self.client.write() {
+            *current = client;
+        }
+        log::info!("Network settings applied");
+        Ok(())
+    }
+
+    /// Server synced with
+    pub fn server_url(&self) -> String {
+        self.server_url
+            .read()
+            .map(|url| url.clone())
+            .unwrap_or_default()
+    }
+
+    /// Switch to another server; the sync position starts over, as on a
+    /// first launch
+    pub(crate) fn set_server_url(&self, server_url: &str) {
+        if let Ok(mut current) = self.server_url.write() {
+            if current.trim_end_matches('/') == server_url.trim_end_matches('/') {
+                return;
+            }
+            log::info!("Server changed from {} to {}", current, server_url);
+            *current = server_url.to_string();
+        }
+
+        crate::auth::restore_session(&self.cookies, server_url);
+        if let Ok(mut account) = self.account.lock() {
+            *account = None;
+        }
+        if let Ok(mut pending) = self.conflict.lock() {
+            *pending = None;
+        }
+        for last in [&self.last_pushed, &self.last_pulled] {
+            if let Ok(mut last) = last.lock() {
+                *last = None;
+            }
+        }
+        if let Ok(mut synced) = self.synced_fingerprint.lock() {
+            *synced = None;
+        }
+        self.last_server_index.store(0, Ordering::SeqCst);
+        self.save_cursor();
+
+        // Backoff and the push channel belong to the old server
+        self.connection.retry_now();
+        self.push_channel.reconnect();
+    }
+
+    /// Whether automatic sync is on and not paused
+    pub fn auto_sync_active(&self) -> bool {
+        self.auto_sync_enabled.load(Ordering::SeqCst) && !self.pause.is_paused()
+    }
+
+    /// Email of the logged-in account, if known
+    pub fn account(&self) -> Option<String> {
+        self.account.lock().ok().and_then(|account| account.clone())
+    }
+
+    /// Remember the logged-in account.
+    ///
+    /// Switching to a different account resets the sync cursor.
+    pub(crate) fn set_account(&self, account: Option<String>) {
+        let Some(account) = account else {
+            if let Ok(mut current) = self.account.lock() {
+                *current = None;
+            }
+            return;
+        };
+
+        let cursor = SyncCursor::load().unwrap_or_default();
+        if cursor
+            .account
+            .as_ref()
+            .is_some_and(|previous| *previous != account)
+        {
+            log::info!("Account changed, resetting sync cursor");
+            self.last_server_index.store(0, Ordering::SeqCst);
+        }
+
+        if let Ok(mut current) = self.account.lock() {
+            *current = Some(account);
+        }
+        self.save_cursor();
+    }
+
+    /// Record a new server index, mark the local clipboard as in sync and
+    /// persist the cursor
+    pub fn set_server_index(&self, index: u64) {
+        if let Ok(mut synced) = self.synced_fingerprint.lock() {
+            *synced = crate::clipboard::last_fingerprint();
+        }
+        self.skip_to_index(index);
+    }
+
+    /// Record an item written from the server to the local clipboard
+    pub fn record_pulled(&self, index: u64) {
+        let item = crate::clipboard::last_fingerprint()
+            .map(|fingerprint| SyncedItem { index, fingerprint });
+        if let Ok(mut last) = self.last_pulled.lock() {
+            *last = item;
+        }
+        self.set_server_index(index);
+    }
+
+    /// Record an item pushed from the local clipboard
+    fn record_pushed(&self, index: u64, fingerprint: Option<Fingerprint>) {
+        let item = fingerprint.map(|fingerprint| SyncedItem { index, fingerprint });
+        if let Ok(mut last) = self.last_pushed.lock() {
+            *last = item;
+        }
+        self.set_server_index(index);
+    }
+
+    /// Whether content with this fingerprint is already the current server
+    /// item, because this device just pushed or pulled it
+    pub fn is_current_item(&self, fingerprint: Fingerprint) -> bool {
+        let current = SyncedItem {
+            index: self.last_server_index.load(Ordering::SeqCst),
+            fingerprint,
+        };
+        [&self.last_pushed, &self.last_pulled]
+            .iter()
+            .any(|last| last.lock().is_ok_and(|last| *last == Some(current)))
+    }
+
+    /// Record a new server index without touching the local sync state
+    fn skip_to_index(&self, index: u64) {
+        self.last_server_index.store(index, Ordering::SeqCst);
+        self.save_cursor();
+    }
+
+    /// Whether the local clipboard changed since it was last in sync
+    pub fn local_changed(&self) -> bool {
+        self.synced_fingerprint()
+            .is_some_and(|synced| Some(synced) != crate::clipboard::last_fingerprint())
+    }
+
+    /// Fingerprint of the local clipboard when it was last in sync
+    fn synced_fingerprint(&self) -> Option<Fingerprint> {
+        self.synced_fingerprint
+            .lock()
+            .ok()
+            .and_then(|synced| *synced)
+    }
+
+    /// Last item pulled into the local clipboard
+    pub fn last_pull(&self) -> Option<SyncActivity> {
+        self.last_pull_activity
+            .lock()
+            .ok()
+            .and_then(|last| last.clone())
+    }
+
+    /// Last item pushed to the server
+    pub fn last_push(&self) -> Option<SyncActivity> {
+        self.last_push_activity
+            .lock()
+            .ok()
+            .and_then(|last| last.clone())
+    }
+
+    /// Remember a pull or push for the status display
+    pub(crate) fn record_activity(&self, direction: TransferDirection, activity: SyncActivity) {
+        let last = match direction {
+            TransferDirection::Download => &self.last_pull_activity,
+            TransferDirection::Upload => &self.last_push_activity,
+        };
+        if let Ok(mut last) = last.lock() {
+            *last = Some(activity);
+        }
+    }
+
+    /// Persist the current sync position
+    fn save_cursor(&self) {
+        let cursor = SyncCursor {
+            server_url: self.server_url(),
+            account: self.account(),
+            last_index: self.last_server_index.load(Ordering::SeqCst),
+            last_local_fingerprint: self.synced_fingerprint(),
+            last_pushed: self.last_pushed.lock().ok().and_then(|last| *last),
+            last_pulled: self.last_pulled.lock().ok().and_then(|last| *last),
+        };
+        if let Err(e) = cursor.save() {
+            log::error!("Failed to save sync cursor: {}", e);
+        }
+    }
+}
+
+/// Item pulled or pushed at some point
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncActivity {
+    /// Unix time in milliseconds
+    pub at: u64,
+    pub content_type: String,
+    /// Device the item came from, for pulled items
+    pub device: Option<String>,
+}
+
+impl SyncActivity {
+    pub(crate) fn now(content_type: &str, device: Option<String>) -> Self {
+        Self {
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            content_type: content_type.to_string(),
+            device,
         }
     }
 }
 
 /// Sync status for frontend
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncStatus {
+    /// Automatic sync is on and not paused
     pub auto_sync_enabled: bool,
+    pub paused_remaining_secs: Option<u64>,
     pub is_syncing: bool,
     pub last_server_index: u64,
+    pub connection: ConnectionState,
+    pub consecutive_failures: u32,
+    pub next_retry_secs: Option<u64>,
+    pub last_error: Option<AppError>,
+    pub outbox_pending: usize,
+    pub push_channel: ChannelState,
+    pub last_pull: Option<SyncActivity>,
+    pub last_push: Option<SyncActivity>,
 }
 
 /// Sync event types
@@ -45,60 +376,226 @@ pub struct SyncStatus {
 #[serde(tag = "type")]
 pub enum SyncEvent {
     Started,
-    Pulled { content_type: String },
-    Pushed { content_type: String },
-    Error { message: String },
+    Pulled {
+        content_type: String,
+        /// Name of the device that pushed the item, if known
+        device: Option<String>,
+        file_name: Option<String>,
+        /// Size of the content in bytes
+        size: u64,
+        index: u64,
+    },
+    Pushed {
+        content_type: String,
+    },
+    /// The clipboard already is the current server item, so nothing was pushed
+    Unchanged {
+        content_type: String,
+    },
+    Error(AppError),
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+    OutboxChanged {
+        pending: usize,
+    },
+    Progress {
+        id: u64,
+        direction: TransferDirection,
+        bytes: u64,
+        total: Option<u64>,
+    },
+    Conflict {
+        content_type: String,
+    },
+    /// A payload was refused for exceeding the server's content length limit
+    TooLarge {
+        content_type: String,
+        size: u64,
+        limit: Option<u64>,
+    },
+    AutoSyncChanged {
+        enabled: bool,
+        paused_remaining_secs: Option<u64>,
+    },
     Completed,
 }
 
+/// Result of a pull
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PullOutcome {
+    /// Nothing new on the server
+    Unchanged,
+    /// The remote item was written to the local clipboard
+    Applied,
+    /// Conflict resolved in favor of the local clipboard, which should be pushed
+    KeptLocal,
+    /// Conflict waiting for the user
+    Pending,
+}
+
+/// Update the connection state from a request result
+pub(crate) async fn track<T>(app: &AppHandle, result: Result<T, AppError>) -> Result<T, AppError> {
+    let state = app.state::<crate::AppState>();
+    let url = state.sync_state.server_url();
+    let pins = &state.sync_state.pins;
+    let result = result.map_err(|e| pins.explain(&url, e));
+    if !matches!(
+        result,
+        Err(AppError::Network(_) | AppError::CertificateMismatch { .. })
+    ) {
+        pins.confirm(&url);
+    }
+
+    if let Err(e @ AppError::CertificateMismatch { .. }) = &result {
+        let _ = app.emit("sync-event", SyncEvent::Error(e.clone()));
+    }
+
+    let changed = match &result {
+        Ok(_) => state.sync_state.connection.record_success(),
+        Err(e) => {
+            let policy = state.config.lock().await.reconnect.clone();
+            state.sync_state.connection.record_failure(e, &policy)
+        }
+    };
+
+    if let Some(new_state) = changed {
+        match new_state {
+            ConnectionState::Online => crate::tray::set_server_problem(app, None),
+            ConnectionState::Unauthorized => crate::notifications::auth_expired(app),
+            _ => {}
+        }
+        crate::tray::refresh_status(app);
+        let _ = app.emit(
+            "sync-event",
+            SyncEvent::ConnectionChanged { state: new_state },
+        );
+    }
+
+    result
+}
+
+/// Reflect a change of the automatic sync state in the tray and frontend
+pub(crate) fn auto_sync_changed(app: &AppHandle) {
+    let state = app.state::<crate::AppState>();
+    crate::tray::refresh_auto_sync(app);
+    let _ = app.emit(
+        "sync-event",
+        SyncEvent::AutoSyncChanged {
+            enabled: state.sync_state.auto_sync_active(),
+            paused_remaining_secs: state
+                .sync_state
+                .pause
+                .remaining()
+                .map(|left| left.as_secs()),
+        },
+    );
+}
+
 /// Start the background sync loop
 pub async fn start_background_sync(app: AppHandle) {
     log::info!("Starting background sync");
 
-    let config = AppConfig::load().unwrap_or_default();
-    let interval = Duration::from_secs(config.sync_interval);
+    // Learn the account so the right paired key is used
+    crate::auth::refresh_account(&app).await;
+    crate::limits::refresh_limits(&app).await;
+    crate::health::check_configured_server(&app).await;
 
     loop {
-        tokio::time::sleep(interval).await;
-
         let state = app.state::<crate::AppState>();
+        let channel = &state.sync_state.push_channel;
 
-        if !state.sync_state.auto_sync_enabled.load(Ordering::SeqCst) {
+        // While the push channel is connected, polling is only a safety net
+        let interval = {
+            let config = state.config.lock().await;
+            if channel.is_connected() {
+                Duration::from_secs(config.push_channel.poll_interval_secs.max(1))
+            } else {
+                state.sync_state.schedule.next(&config)
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = channel.woken() => {}
+        }
+
+        if state.sync_state.pause.take_expired() {
+            log::info!("Auto sync pause ended");
+            auto_sync_changed(&app);
+        }
+
+        if !state.sync_state.auto_sync_active() {
+            // Keep the remaining time in the tray current
+            if state.sync_state.pause.is_paused() {
+                crate::tray::refresh_auto_sync(&app);
+            }
+            continue;
+        }
+
+        // Back off while the server is unreachable
+        if !state.sync_state.connection.should_attempt() {
             continue;
         }
 
         if state.sync_state.is_syncing.swap(true, Ordering::SeqCst) {
             continue; // Already syncing
         }
+        crate::tray::refresh_status(&app);
+
+        // A conflict the user left unanswered falls back to the default policy
+        if let Err(e) = crate::conflict::expire(&app).await {
+            log::error!("Failed to resolve expired conflict: {}", e);
+        }
+
+        // Deliver pushes queued while offline
+        if !state.sync_state.outbox.is_empty() {
+            let flushed = flush_outbox(&app).await;
+            if let Err(e) = track(&app, flushed).await {
+                log::debug!("Outbox flush failed: {}", e);
+            }
+        }
 
         // Try to pull from server
-        if let Err(e) = pull_from_server(&app, &state.sync_state).await {
-            log::debug!("Pull failed (might be normal): {}", e);
+        let result = pull_from_server(&app, &state.sync_state).await;
+        match track(&app, result).await {
+            Ok(PullOutcome::KeptLocal) => {
+                if let Ok(content) = read_clipboard() {
+                    let pushed = push_to_server(&app, content).await;
+                    if let Err(e) = track(&app, pushed).await {
+                        log::error!("Push failed: {}", e);
+                        crate::notifications::push_failed(&app, &e);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("Pull failed: {}", e),
         }
 
         state.sync_state.is_syncing.store(false, Ordering::SeqCst);
+        crate::tray::refresh_status(&app);
     }
 }
 
 /// Pull clipboard content from server
-async fn pull_from_server(app: &AppHandle, state: &SyncState) -> Result<(), String> {
+async fn pull_from_server(app: &AppHandle, state: &SyncState) -> Result<PullOutcome, AppError> {
     let current_index = state.last_server_index.load(Ordering::SeqCst);
+    let cipher = crate::pairing::current_cipher(app).await;
 
     let resp = state
-        .client
-        .get(&format!("{}/api/v1/clipboard", state.server_url))
+        .client()
+        .get(format!("{}/api/v1/clipboard", state.server_url()))
         .header("X-Index", current_index.to_string())
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     // 304 means no new data
     if resp.status() == 304 {
-        return Ok(());
+        return Ok(PullOutcome::Unchanged);
     }
 
     if !resp.status().is_success() {
-        return Err(format!("Server error: {}", resp.status()));
+        return Err(AppError::from_response(resp).await);
     }
 
     // Parse response headers before consuming response
@@ -116,63 +613,435 @@ async fn pull_from_server(app: &AppHandle, state: &SyncState) -> Result<(), Stri
         .unwrap_or("text")
         .to_string();
 
+    let mime_type = resp
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let file_name = resp
+        .headers()
+        .get("x-filename")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let device = crate::device::client_name(
+        resp.headers()
+            .get(crate::device::CLIENT_NAME_HEADER)
+            .and_then(|v| v.to_str().ok()),
+    );
+
+    let created_at: Option<u64> = resp
+        .headers()
+        .get("x-createdat")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse().ok());
+
     // No new data
     if new_index == 0 || new_index == current_index {
-        return Ok(());
+        return Ok(PullOutcome::Unchanged);
+    }
+
+    // Files are not written to the clipboard; skip them, and types this
+    // client does not know, without downloading them
+    if !matches!(content_type.as_str(), "text" | "screenshot") {
+        log::debug!(
+            "Unsupported type {}, skipping index {}",
+            content_type,
+            new_index
+        );
+        state.skip_to_index(new_index);
+        return Ok(PullOutcome::Unchanged);
+    }
+
+    // Skip types this device does not receive, without downloading them
+    let direction = app
+        .state::<crate::AppState>()
+        .config
+        .lock()
+        .await
+        .direction_for(&content_type);
+    if !direction.can_receive() {
+        log::debug!(
+            "Not receiving {}, skipping index {}",
+            content_type,
+            new_index
+        );
+        state.skip_to_index(new_index);
+        return Ok(PullOutcome::Unchanged);
     }
 
-    // Get data (consumes response)
-    let data = resp.bytes().await.map_err(|e| e.to_string())?;
+    // Get data (consumes response); a cancelled item is skipped, not retried
+    let data = match transfer::download(app, resp).await {
+        Ok(data) => data,
+        Err(AppError::Cancelled) => {
+            state.skip_to_index(new_index);
+            return Err(AppError::Cancelled);
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Remove end-to-end encryption and compression; payloads we cannot open
+    // are skipped rather than written to the clipboard as garbage
+    let opened = match crate::compression::parse_content_type(&mime_type) {
+        Ok((mime_type, compression)) => match cipher.open(data, &mime_type).await {
+            Ok(data) => crate::compression::decompress(data, compression).await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    let data = match opened {
+        Ok(data) => data,
+        Err(e) => {
+            state.skip_to_index(new_index);
+            let _ = app.emit("sync-event", SyncEvent::Error(e.clone()));
+            return Err(e);
+        }
+    };
+
+    let file_name = if file_name.is_empty() {
+        None
+    } else {
+        let opened = cipher.open_file_name(&file_name).await.and_then(|name| {
+            urlencoding::decode(&name)
+                .map(|name| name.into_owned())
+                .map_err(|e| AppError::Protocol(e.to_string()))
+        });
+        match opened {
+            Ok(file_name) => {
+                log::debug!("Pulled item file name: {}", file_name);
+                Some(file_name)
+            }
+            Err(e) => {
+                state.skip_to_index(new_index);
+                let _ = app.emit("sync-event", SyncEvent::Error(e.clone()));
+                return Err(e);
+            }
+        }
+    };
+    let size = data.len() as u64;
 
     // Convert to clipboard content
     let content = match content_type.as_str() {
         "text" => ClipboardContent::Text(String::from_utf8_lossy(&data).to_string()),
-        "screenshot" => ClipboardContent::Image(data.to_vec()),
-        _ => return Ok(()), // Unsupported type
+        _ => ClipboardContent::Image(data), // "screenshot", checked above
     };
 
+    // Both sides changed since the last sync
+    if state.local_changed() {
+        let policy = app
+            .state::<crate::AppState>()
+            .config
+            .lock()
+            .await
+            .conflict_policy;
+
+        let choice = crate::conflict::decide(
+            policy,
+            direction.can_send(),
+            created_at,
+            crate::clipboard::last_local_change_at(),
+        );
+        let remote_wins = match choice {
+            Some(choice) => choice == ConflictChoice::Remote,
+            None => {
+                state.skip_to_index(new_index);
+                crate::conflict::ask(
+                    app,
+                    PendingConflict::new(new_index, content_type, device, created_at, content),
+                );
+                return Ok(PullOutcome::Pending);
+            }
+        };
+
+        if !remote_wins {
+            log::info!("Sync conflict, keeping local clipboard ({:?})", policy);
+            state.skip_to_index(new_index);
+            return Ok(PullOutcome::KeptLocal);
+        }
+
+        log::info!(
+            "Sync conflict, taking remote {} ({:?})",
+            content_type,
+            policy
+        );
+    }
+
     // Write to system clipboard
-    write_clipboard(content)?;
+    write_clipboard(content.clone())?;
+    crate::notifications::received(app, content.clone(), device.clone());
+    crate::recent::record(app, content, device.clone());
 
     // Update index
-    state.last_server_index.store(new_index, Ordering::SeqCst);
+    state.record_pulled(new_index);
+    state.schedule.activity();
+
+    // A newer remote item supersedes any conflict still waiting
+    if let Ok(mut pending) = state.conflict.lock() {
+        *pending = None;
+    }
+
+    log::info!(
+        "Pulled {} from {}, index: {}",
+        content_type,
+        device.as_deref().unwrap_or("unknown device"),
+        new_index
+    );
+    state.record_activity(
+        TransferDirection::Download,
+        SyncActivity::now(&content_type, device.clone()),
+    );
+    crate::tray::refresh_status(app);
 
     // Notify frontend
     let _ = app.emit(
         "sync-event",
         SyncEvent::Pulled {
-            content_type: content_type.clone(),
+            content_type,
+            device,
+            file_name,
+            size,
+            index: new_index,
         },
     );
 
-    log::info!("Pulled {} from server, index: {}", content_type, new_index);
+    Ok(PullOutcome::Applied)
+}
+
+/// Push clipboard content to server
+pub async fn push_to_server(app: &AppHandle, content: ClipboardContent) -> Result<(), AppError> {
+    let (data, content_type) = match content {
+        ClipboardContent::Text(text) => (text.into_bytes(), "text"),
+        ClipboardContent::Image(img) => (img, "screenshot"),
+    };
+
+    let state = app.state::<crate::AppState>();
+    if !state
+        .config
+        .lock()
+        .await
+        .direction_for(content_type)
+        .can_send()
+    {
+        log::debug!("Not sending {}, skipping push", content_type);
+        return Ok(());
+    }
+
+    // Pushing the current server item again would only make every other
+    // device download it once more
+    if state
+        .sync_state
+        .is_current_item(Fingerprint::of_payload(content_type, &data))
+    {
+        log::debug!("{} is already the current server item", content_type);
+        let _ = app.emit(
+            "sync-event",
+            SyncEvent::Unchanged {
+                content_type: content_type.to_string(),
+            },
+        );
+        return Ok(());
+    }
+
+    push_or_queue(app, data, content_type).await
+}
+
+/// Push a payload, queueing it in the outbox when the server is unreachable
+async fn push_or_queue(app: &AppHandle, data: Vec<u8>, content_type: &str) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+    let (policy, compression) = {
+        let config = state.config.lock().await;
+        (config.outbox.clone(), config.compression.clone())
+    };
+    let fingerprint = Fingerprint::of_payload(content_type, &data);
+    let (data, compression) = crate::compression::compress(data, &compression).await?;
+    let data = Bytes::from(data);
+
+    // Neither sending nor queueing helps when the server will refuse it
+    check_size(app, content_type, data.len() as u64)?;
+
+    if !policy.enabled {
+        return push_payload(app, data, content_type, compression, Some(fingerprint)).await;
+    }
+
+    // Older items are still waiting; queue behind them to keep the order
+    if !state.sync_state.outbox.is_empty() {
+        state
+            .sync_state
+            .outbox
+            .enqueue(&data, content_type, compression, fingerprint, &policy)?;
+        emit_outbox_changed(app, &state.sync_state.outbox);
+        return flush_outbox(app).await;
+    }
+
+    match push_payload(
+        app,
+        data.clone(),
+        content_type,
+        compression,
+        Some(fingerprint),
+    )
+    .await
+    {
+        Err(e) if e.is_connection_error() => {
+            state.sync_state.outbox.enqueue(
+                &data,
+                content_type,
+                compression,
+                fingerprint,
+                &policy,
+            )?;
+            emit_outbox_changed(app, &state.sync_state.outbox);
+            log::info!("Queued {} in outbox: {}", content_type, e);
+            Err(e)
+        }
+        result => result,
+    }
+}
+
+/// Push queued items in order, stopping at the first connection failure
+async fn flush_outbox(app: &AppHandle) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+    let outbox = &state.sync_state.outbox;
+
+    while let Some((item, data)) = outbox.front()? {
+        // The direction may have changed since the item was queued
+        let can_send = state
+            .config
+            .lock()
+            .await
+            .direction_for(&item.content_type)
+            .can_send();
+        if !can_send {
+            log::info!(
+                "Not sending {}, dropping outbox item {}",
+                item.content_type,
+                item.id
+            );
+            outbox.remove(item.id)?;
+            emit_outbox_changed(app, outbox);
+            continue;
+        }
+
+        let result = push_payload(
+            app,
+            Bytes::from(data),
+            &item.content_type,
+            item.compression,
+            item.fingerprint,
+        )
+        .await;
+
+        match result {
+            Ok(()) => {}
+            Err(e) if e.is_connection_error() => return Err(e),
+            Err(AppError::Cancelled) => log::info!("Cancelled outbox item {}", item.id),
+            Err(e) => {
+                // Retrying will not help; drop it and report why
+                log::error!("Dropping outbox item {}: {}", item.id, e);
+                crate::notifications::push_failed(app, &e);
+                let _ = app.emit("sync-event", SyncEvent::Error(e));
+            }
+        }
+
+        outbox.remove(item.id)?;
+        emit_outbox_changed(app, outbox);
+    }
 
     Ok(())
 }
 
-/// Push clipboard content to server
-pub async fn push_to_server(app: &AppHandle, content: &ClipboardContent) -> Result<(), String> {
+/// Refuse a payload over the server's limit and tell the frontend why
+fn check_size(app: &AppHandle, content_type: &str, size: u64) -> Result<(), AppError> {
     let state = app.state::<crate::AppState>();
-    let config = state.config.lock().await;
+    state
+        .sync_state
+        .limits
+        .check(size)
+        .map_err(|e| too_large(app, content_type, e))
+}
 
-    let (data, content_type) = match content {
-        ClipboardContent::Text(text) => (text.as_bytes().to_vec(), "text"),
-        ClipboardContent::Image(img) => (img.clone(), "screenshot"),
+/// Emit a too-large event for a size error, passing the error through
+fn too_large(app: &AppHandle, content_type: &str, error: AppError) -> AppError {
+    if let AppError::TooLarge {
+        size: Some(size),
+        limit,
+        ..
+    } = &error
+    {
+        log::info!("{} too large to sync: {}", content_type, error);
+        crate::notifications::too_large(app, content_type, &error);
+        let _ = app.emit(
+            "sync-event",
+            SyncEvent::TooLarge {
+                content_type: content_type.to_string(),
+                size: *size,
+                limit: *limit,
+            },
+        );
+    }
+    error
+}
+
+/// Notify the frontend about the outbox size
+fn emit_outbox_changed(app: &AppHandle, outbox: &Outbox) {
+    let _ = app.emit(
+        "sync-event",
+        SyncEvent::OutboxChanged {
+            pending: outbox.len(),
+        },
+    );
+}
+
+/// Upload a payload to the server, encrypting it when configured
+async fn push_payload(
+    app: &AppHandle,
+    data: Bytes,
+    content_type: &str,
+    compression: CompressionAlgorithm,
+    fingerprint: Option<Fingerprint>,
+) -> Result<(), AppError> {
+    let cipher = crate::pairing::current_cipher(app).await;
+    let state = app.state::<crate::AppState>();
+    let (server_url, device_name) = {
+        let config = state.config.lock().await;
+        (config.server_url.clone(), config.device_name())
     };
 
-    let resp = state
+    let mime_type = crate::compression::content_type(
+        if cipher.is_active() {
+            crypto::ENCRYPTED_MIME_TYPE
+        } else {
+            "application/octet-stream"
+        },
+        compression,
+    );
+    let data = if cipher.is_active() {
+        Bytes::from(cipher.seal(data.to_vec()).await?)
+    } else {
+        data
+    };
+    check_size(app, content_type, data.len() as u64)?;
+
+    let size = data.len() as u64;
+    let request = state
         .sync_state
-        .client
-        .post(&format!("{}/api/v1/clipboard", config.server_url))
-        .header("Content-Type", "application/octet-stream")
+        .client()
+        .post(format!("{}/api/v1/clipboard", server_url))
+        .header("Content-Type", mime_type)
         .header("X-Type", content_type)
-        .body(data)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .header(
+            crate::device::CLIENT_NAME_HEADER,
+            urlencoding::encode(&device_name).into_owned(),
+        );
+    let resp = transfer::upload(app, request, data).await?;
 
     if !resp.status().is_success() {
-        return Err(format!("Server error: {}", resp.status()));
+        let e = AppError::from_response(resp).await;
+        let e = state.sync_state.limits.learn(e, size);
+        return Err(too_large(app, content_type, e));
     }
 
     // Update index
@@ -182,12 +1051,21 @@ pub async fn push_to_server(app: &AppHandle, content: &ClipboardContent) -> Resu
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.parse::<u64>().ok())
     {
-        state
-            .sync_state
-            .last_server_index
-            .store(index, Ordering::SeqCst);
+        state.sync_state.schedule.activity();
+        state.sync_state.record_pushed(index, fingerprint);
+
+        // The local side replaced the remote item of a pending conflict
+        if let Ok(mut pending) = state.sync_state.conflict.lock() {
+            *pending = None;
+        }
     }
 
+    state.sync_state.record_activity(
+        TransferDirection::Upload,
+        SyncActivity::now(content_type, None),
+    );
+    crate::tray::refresh_status(app);
+
     // Notify frontend
     let _ = app.emit(
         "sync-event",
@@ -203,44 +1081,84 @@ pub async fn push_to_server(app: &AppHandle, content: &ClipboardContent) -> Resu
 
 /// Tauri command: Trigger manual sync
 #[tauri::command]
-pub async fn sync_now(app: AppHandle) -> Result<(), String> {
+pub async fn sync_now(app: AppHandle) -> Result<(), AppError> {
     let state = app.state::<crate::AppState>();
 
     if state.sync_state.is_syncing.swap(true, Ordering::SeqCst) {
-        return Err("Sync already in progress".into());
+        return Err(AppError::SyncInProgress);
     }
 
     let _ = app.emit("sync-event", SyncEvent::Started);
+    crate::tray::refresh_status(&app);
 
-    // First try to push local clipboard
-    if let Ok(content) = read_clipboard() {
-        if let Err(e) = push_to_server(&app, &content).await {
-            log::error!("Push failed: {}", e);
+    // A manual sync always tries right away, even while backing off
+    state.sync_state.connection.retry_now();
+    state.sync_state.schedule.activity();
+
+    let mut result = Ok(());
+
+    // Deliver pushes queued while offline before the current clipboard
+    if !state.sync_state.outbox.is_empty() {
+        let flushed = flush_outbox(&app).await;
+        if let Err(e) = track(&app, flushed).await {
+            log::debug!("Outbox flush failed: {}", e);
         }
     }
 
-    // Then try to pull from server
-    if let Err(e) = pull_from_server(&app, &state.sync_state).await {
-        log::debug!("Pull failed: {}", e);
+    // Pull first so a newer remote item is not overwritten blindly
+    let pulled = pull_from_server(&app, &state.sync_state).await;
+    let outcome = match track(&app, pulled).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            log::debug!("Pull failed: {}", e);
+            result = Err(e);
+            PullOutcome::Unchanged
+        }
+    };
+
+    // Then push the local clipboard, unless it was just replaced or the
+    // user has yet to pick a side; pushing would silently keep the local one
+    let conflict_pending = crate::conflict::expire(&app).await.unwrap_or_else(|e| {
+        log::error!("Failed to resolve expired conflict: {}", e);
+        true
+    });
+    let skip_push = outcome == PullOutcome::Applied || conflict_pending;
+    if !skip_push {
+        match read_clipboard() {
+            Ok(content) => {
+                let pushed = push_to_server(&app, content).await;
+                if let Err(e) = track(&app, pushed).await {
+                    log::error!("Push failed: {}", e);
+                    crate::notifications::push_failed(&app, &e);
+                    let _ = app.emit("sync-event", SyncEvent::Error(e.clone()));
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+            }
+            Err(e) => log::debug!("Nothing to push: {}", e),
+        }
     }
 
     state.sync_state.is_syncing.store(false, Ordering::SeqCst);
 
+    crate::tray::refresh_status(&app);
     let _ = app.emit("sync-event", SyncEvent::Completed);
 
-    Ok(())
+    result
 }
 
-/// Tauri command: Toggle auto sync
+/// Tauri command: Toggle auto sync; toggling while paused resumes
 #[tauri::command]
 pub fn toggle_auto_sync(app: AppHandle) -> bool {
     let state = app.state::<crate::AppState>();
-    let current = state.sync_state.auto_sync_enabled.load(Ordering::SeqCst);
-    let new_value = !current;
+    let new_value = !state.sync_state.auto_sync_active();
+    state.sync_state.pause.resume();
     state
         .sync_state
         .auto_sync_enabled
         .store(new_value, Ordering::SeqCst);
+    auto_sync_changed(&app);
     new_value
 }
 
@@ -248,9 +1166,48 @@ pub fn toggle_auto_sync(app: AppHandle) -> bool {
 #[tauri::command]
 pub fn get_sync_status(app: AppHandle) -> SyncStatus {
     let state = app.state::<crate::AppState>();
+    let connection = &state.sync_state.connection;
     SyncStatus {
-        auto_sync_enabled: state.sync_state.auto_sync_enabled.load(Ordering::SeqCst),
+        auto_sync_enabled: state.sync_state.auto_sync_active(),
+        paused_remaining_secs: state
+            .sync_state
+            .pause
+            .remaining()
+            .map(|left| left.as_secs()),
         is_syncing: state.sync_state.is_syncing.load(Ordering::SeqCst),
         last_server_index: state.sync_state.last_server_index.load(Ordering::SeqCst),
+        connection: connection.state(),
+        consecutive_failures: connection.consecutive_failures(),
+        next_retry_secs: connection.retry_in().map(|left| left.as_secs()),
+        last_error: connection.last_error(),
+        outbox_pending: state.sync_state.outbox.len(),
+        push_channel: state.sync_state.push_channel.state(),
+        last_pull: state.sync_state.last_pull(),
+        last_push: state.sync_state.last_push(),
     }
 }
+
+/// Tauri command: List pushes waiting in the outbox
+#[tauri::command]
+pub fn get_outbox(app: AppHandle) -> Vec<OutboxItem> {
+    let state = app.state::<crate::AppState>();
+    state.sync_state.outbox.items()
+}
+
+/// Tauri command: Cancel a queued push
+#[tauri::command]
+pub fn cancel_outbox_item(app: AppHandle, id: u64) -> Result<bool, AppError> {
+    let state = app.state::<crate::AppState>();
+    let removed = state.sync_state.outbox.remove(id)?;
+    emit_outbox_changed(&app, &state.sync_state.outbox);
+    Ok(removed)
+}
+
+/// Tauri command: Cancel every queued push
+#[tauri::command]
+pub fn clear_outbox(app: AppHandle) -> Result<(), AppError> {
+    let state = app.state::<crate::AppState>();
+    state.sync_state.outbox.clear()?;
+    emit_outbox_changed(&app, &state.sync_state.outbox);
+    Ok(())
+}
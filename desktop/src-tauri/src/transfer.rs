@@ -0,0 +1,210 @@
+//! In-flight transfers
+//!
+//! Uploads and downloads are streamed in chunks, so progress can be reported
+//! to the frontend and a transfer can be cancelled part way.
+
+use crate::error::AppError;
+use crate::sync::SyncEvent;
+use bytes::Bytes;
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+/// Size of the chunks an upload is sent in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Minimum time between two progress events of a transfer
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Direction of a transfer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// Registry of running transfers
+#[derive(Default)]
+pub struct Transfers {
+    next_id: AtomicU64,
+    active: Mutex<HashMap<u64, watch::Sender<bool>>>,
+}
+
+impl Transfers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a transfer of `total` bytes, if known
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        direction: TransferDirection,
+        total: Option<u64>,
+    ) -> Arc<Transfer> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (cancel, cancelled) = watch::channel(false);
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(id, cancel);
+        }
+
+        Arc::new(Transfer {
+            id,
+            direction,
+            total,
+            bytes: AtomicU64::new(0),
+            last_emit: Mutex::new(None),
+            cancelled,
+            app: app.clone(),
+        })
+    }
+
+    /// Cancel one transfer, or all when `id` is `None`; returns how many
+    pub fn cancel(&self, id: Option<u64>) -> usize {
+        let Ok(active) = self.active.lock() else {
+            return 0;
+        };
+
+        let mut cancelled = 0;
+        for (transfer_id, cancel) in active.iter() {
+            if id.is_none_or(|id| id == *transfer_id) {
+                cancel.send_replace(true);
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    fn finish(&self, id: u64) {
+        if let Ok(mut active) = self.active.lock() {
+            active.remove(&id);
+        }
+    }
+}
+
+/// A running transfer
+pub struct Transfer {
+    id: u64,
+    direction: TransferDirection,
+    total: Option<u64>,
+    bytes: AtomicU64,
+    last_emit: Mutex<Option<Instant>>,
+    cancelled: watch::Receiver<bool>,
+    app: AppHandle,
+}
+
+impl Transfer {
+    /// Record transferred bytes and report progress, throttled for the UI
+    fn advance(&self, len: usize) {
+        let bytes = self.bytes.fetch_add(len as u64, Ordering::SeqCst) + len as u64;
+        let done = self.total.is_some_and(|total| bytes >= total);
+
+        if let Ok(mut last_emit) = self.last_emit.lock() {
+            if !done && last_emit.is_some_and(|at| at.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last_emit = Some(Instant::now());
+        }
+
+        let _ = self.app.emit(
+            "sync-event",
+            SyncEvent::Progress {
+                id: self.id,
+                direction: self.direction,
+                bytes,
+                total: self.total,
+            },
+        );
+    }
+
+    /// Resolve once the transfer is cancelled
+    async fn cancelled(&self) {
+        let mut cancelled = self.cancelled.clone();
+        if cancelled.wait_for(|cancelled| *cancelled).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        if let Some(state) = self.app.try_state::<crate::AppState>() {
+            state.sync_state.transfers.finish(self.id);
+        }
+    }
+}
+
+/// Send a request with `data` streamed as its body
+pub async fn upload(
+    app: &AppHandle,
+    request: RequestBuilder,
+    data: Bytes,
+) -> Result<Response, AppError> {
+    let state = app.state::<crate::AppState>();
+    let total = data.len();
+    let transfer =
+        state
+            .sync_state
+            .transfers
+            .start(app, TransferDirection::Upload, Some(total as u64));
+
+    let chunks: Vec<Bytes> = (0..total)
+        .step_by(CHUNK_SIZE)
+        .map(|start| data.slice(start..(start + CHUNK_SIZE).min(total)))
+        .collect();
+    let progress = transfer.clone();
+    let body = futures_util::stream::iter(chunks.into_iter().map(move |chunk| {
+        progress.advance(chunk.len());
+        Ok::<_, std::io::Error>(chunk)
+    }));
+
+    // The server expects a length rather than a chunked body
+    let request = request
+        .header("Content-Length", total)
+        .body(reqwest::Body::wrap_stream(body));
+
+    tokio::select! {
+        resp = request.send() => Ok(resp?),
+        _ = transfer.cancelled() => Err(AppError::Cancelled),
+    }
+}
+
+/// Read a response body in chunks
+pub async fn download(app: &AppHandle, mut resp: Response) -> Result<Vec<u8>, AppError> {
+    let state = app.state::<crate::AppState>();
+    let total = resp.content_length();
+    let transfer = state
+        .sync_state
+        .transfers
+        .start(app, TransferDirection::Download, total);
+
+    // Do not trust the announced length with a large allocation
+    let mut data = Vec::with_capacity(total.unwrap_or_default().min(16 << 20) as usize);
+    loop {
+        tokio::select! {
+            chunk = resp.chunk() => match chunk? {
+                Some(chunk) => {
+                    data.extend_from_slice(&chunk);
+                    transfer.advance(chunk.len());
+                }
+                None => return Ok(data),
+            },
+            _ = transfer.cancelled() => return Err(AppError::Cancelled),
+        }
+    }
+}
+
+/// Tauri command: Cancel a running transfer, or all of them without an id
+#[tauri::command]
+pub fn cancel_transfer(app: AppHandle, id: Option<u64>) -> usize {
+    let state = app.state::<crate::AppState>();
+    let cancelled = state.sync_state.transfers.cancel(id);
+    log::info!("Cancelled {} transfer(s)", cancelled);
+    cancelled
+}
@@ -1,27 +1,420 @@
 //! System tray implementation
 //!
-//! Creates and manages the system tray icon and menu.
+//! Creates and manages the system tray icon and menu. The top of the menu
+//! shows the account, the sync state and the last pull and push; the icon
+//! carries a badge for the sync state.
 
+use crate::config::SyncDirection;
+use crate::connection::ConnectionState;
+use crate::pause::PauseFor;
+use crate::sync::SyncActivity;
+use chrono::{Local, TimeZone};
+use std::sync::Mutex;
 use tauri::{
-    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager,
+    AppHandle, Emitter, Manager, Wry,
 };
 
+/// Tray menu items whose state follows the app state
+pub struct TrayItems {
+    auto_sync: CheckMenuItem<Wry>,
+    pause_menu: Submenu<Wry>,
+    resume: MenuItem<Wry>,
+    direction: Vec<(SyncDirection, CheckMenuItem<Wry>)>,
+    account: MenuItem<Wry>,
+    status: MenuItem<Wry>,
+    last_pull: MenuItem<Wry>,
+    last_push: MenuItem<Wry>,
+    recent_menu: Submenu<Wry>,
+    recent_repush: CheckMenuItem<Wry>,
+    /// Problem found by the last server check
+    problem: Mutex<Option<String>>,
+    /// State the icon currently shows
+    shown: Mutex<Option<TrayState>>,
+}
+
+/// Sync state shown by the tray icon
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TrayState {
+    Idle,
+    Syncing,
+    Paused,
+    Offline,
+    Error,
+}
+
+impl TrayState {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Idle => "已同步 Up to date",
+            Self::Syncing => "同步中 Syncing",
+            Self::Paused => "已暂停 Paused",
+            Self::Offline => "离线 Offline",
+            Self::Error => "错误 Error",
+        }
+    }
+
+    /// Color of the icon badge, if the state has one
+    fn badge(self) -> Option<[u8; 3]> {
+        match self {
+            Self::Idle | Self::Offline => None,
+            Self::Syncing => Some([0x25, 0x63, 0xeb]),
+            Self::Paused => Some([0xf5, 0x9e, 0x0b]),
+            Self::Error => Some([0xdc, 0x26, 0x26]),
+        }
+    }
+}
+
+/// Label of the pause submenu while not paused
+const PAUSE_LABEL: &str = "暂停 Pause";
+
+/// Tray tooltip while the server is fine
+const TOOLTIP: &str = "GCopy - 剪贴板同步";
+
+/// Show a server problem in the tray, or clear it with `None`
+pub fn set_server_problem(app: &AppHandle, problem: Option<&str>) {
+    if let Some(items) = app.try_state::<TrayItems>() {
+        if let Ok(mut current) = items.problem.lock() {
+            *current = problem.map(str::to_string);
+        }
+    }
+    refresh_status(app);
+}
+
+/// Update the account, sync state, last activity and icon from the app state
+pub fn refresh_status(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayItems>() else {
+        return;
+    };
+    let state = app.state::<crate::AppState>();
+    let sync_state = &state.sync_state;
+    let problem = items.problem.lock().ok().and_then(|p| p.clone());
+    let connection = sync_state.connection.state();
+
+    let (tray_state, detail) = if connection == ConnectionState::Unauthorized {
+        (TrayState::Error, Some("未登录 Not logged in".to_string()))
+    } else if let Some(problem) = problem {
+        (TrayState::Error, Some(problem))
+    } else if connection == ConnectionState::CertificateMismatch {
+        let error = sync_state.connection.last_error();
+        (TrayState::Error, error.map(|e| e.to_string()))
+    } else if connection == ConnectionState::Offline {
+        let error = sync_state.connection.last_error();
+        (TrayState::Offline, error.map(|e| e.to_string()))
+    } else if sync_state.is_syncing.load(std::sync::atomic::Ordering::SeqCst) {
+        (TrayState::Syncing, None)
+    } else if !sync_state.auto_sync_active() {
+        (TrayState::Paused, None)
+    } else {
+        (TrayState::Idle, None)
+    };
+
+    let account = match sync_state.account() {
+        Some(account) => format!("账户 Account: {}", account),
+        None => "未登录 Not logged in".to_string(),
+    };
+    let status = format!("状态 Status: {}", tray_state.label());
+    let _ = items.account.set_text(account);
+    let _ = items.status.set_text(&status);
+    let last_pull = activity_label("上次接收 Last received", sync_state.last_pull());
+    let last_push = activity_label("上次发送 Last sent", sync_state.last_push());
+    let _ = items.last_pull.set_text(last_pull);
+    let _ = items.last_push.set_text(last_push);
+
+    let Some(tray) = app.tray_by_id("main") else {
+        return;
+    };
+    let tooltip = match &detail {
+        Some(detail) => format!("{}\n{}: {}", TOOLTIP, status, detail),
+        None => format!("{}\n{}", TOOLTIP, status),
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+
+    // Only touch the icon when the state changed
+    let Ok(mut shown) = items.shown.lock() else {
+        return;
+    };
+    if *shown == Some(tray_state) {
+        return;
+    }
+    if let Some(base) = app.default_window_icon() {
+        let _ = tray.set_icon(Some(state_icon(base, tray_state)));
+        // Template icons are drawn monochrome on macOS, which hides the badge
+        let _ = tray.set_icon_as_template(tray_state.badge().is_none());
+    }
+    *shown = Some(tray_state);
+}
+
+/// List the recent items in the tray
+pub fn refresh_recent(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayItems>() else {
+        return;
+    };
+    let state = app.state::<crate::AppState>();
+    let menu = &items.recent_menu;
+
+    if let Ok(existing) = menu.items() {
+        for item in existing {
+            let _ = menu.remove(&item);
+        }
+    }
+
+    let recent = state.sync_state.recent.items();
+    if recent.is_empty() {
+        if let Ok(empty) = MenuItem::with_id(app, "recent_empty", "暂无 Nothing yet", false, None::<&str>) {
+            let _ = menu.append(&empty);
+        }
+    }
+    for item in recent {
+        let time = Local
+            .timestamp_millis_opt(item.at as i64)
+            .single()
+            .map(|at| at.format("%H:%M").to_string())
+            .unwrap_or_default();
+        // "&" marks a mnemonic in menu labels
+        let label = match &item.device {
+            Some(device) => format!("{}  {} ← {}", time, item.label, device),
+            None => format!("{}  {}", time, item.label),
+        }
+        .replace('&', "&&");
+        match MenuItem::with_id(app, format!("recent_{}", item.id), label, true, None::<&str>) {
+            Ok(entry) => {
+                let _ = menu.append(&entry);
+            }
+            Err(e) => log::warn!("Failed to add recent item to tray: {}", e),
+        }
+    }
+
+    if let Ok(separator) = PredefinedMenuItem::separator(app) {
+        let _ = menu.append(&separator);
+    }
+    let _ = menu.append(&items.recent_repush);
+}
+
+/// Check the re-push item according to the config
+pub fn set_recent_repush_checked(app: &AppHandle, repush: bool) {
+    if let Some(items) = app.try_state::<TrayItems>() {
+        let _ = items.recent_repush.set_checked(repush);
+    }
+}
+
+/// Save whether picked recent items are pushed again
+fn set_recent_repush(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayItems>() else {
+        return;
+    };
+    let repush = items.recent_repush.is_checked().unwrap_or_default();
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<crate::AppState>();
+        let mut config = state.config.lock().await;
+        config.recent.repush = repush;
+        if let Err(e) = config.save() {
+            log::error!("Failed to save recent items setting: {}", e);
+        }
+    });
+}
+
+/// Copy a recent item again from the tray
+fn restore_recent(app: &AppHandle, id: u64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::recent::restore(&app, id).await {
+            log::error!("Failed to copy recent item: {}", e);
+        }
+    });
+}
+
+/// Menu label of the last pull or push
+fn activity_label(title: &str, activity: Option<SyncActivity>) -> String {
+    let Some(activity) = activity else {
+        return format!("{}: —", title);
+    };
+    let time = Local
+        .timestamp_millis_opt(activity.at as i64)
+        .single()
+        .map(|at| at.format("%H:%M").to_string())
+        .unwrap_or_default();
+    match activity.device {
+        Some(device) => format!(
+            "{}: {} · {} ← {}",
+            title, time, activity.content_type, device
+        ),
+        None => format!("{}: {} · {}", title, time, activity.content_type),
+    }
+}
+
+/// App icon marked for a sync state: grayed out while offline, with a
+/// colored badge in the corner for other states
+fn state_icon(base: &Image<'_>, state: TrayState) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+
+    if state == TrayState::Offline {
+        for pixel in rgba.chunks_exact_mut(4) {
+            let [r, g, b] = [pixel[0] as u32, pixel[1] as u32, pixel[2] as u32];
+            let gray = ((r * 30 + g * 59 + b * 11) / 100) as u8;
+            pixel[..3].fill(gray);
+            pixel[3] /= 2;
+        }
+    }
+
+    if let Some(color) = state.badge() {
+        let radius = width.min(height) as f32 * 0.22;
+        let ring = radius + (width.min(height) as f32 * 0.06).max(1.0);
+        let center_x = width as f32 - ring;
+        let center_y = height as f32 - ring;
+        for y in 0..height {
+            for x in 0..width {
+                let distance = (x as f32 + 0.5 - center_x).hypot(y as f32 + 0.5 - center_y);
+                let offset = ((y * width + x) * 4) as usize;
+                if distance <= radius {
+                    rgba[offset..offset + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+                } else if distance <= ring {
+                    rgba[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+                }
+            }
+        }
+    }
+
+    Image::new_owned(rgba, width, height)
+}
+
+/// Update the auto sync check and the pause status from the sync state
+pub fn refresh_auto_sync(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayItems>() else {
+        return;
+    };
+    let state = app.state::<crate::AppState>();
+    let remaining = state.sync_state.pause.remaining();
+
+    let _ = items.auto_sync.set_checked(state.sync_state.auto_sync_active());
+    let _ = items.resume.set_enabled(remaining.is_some());
+    let label = match remaining {
+        Some(left) => format!("已暂停 Paused ({})", crate::pause::format_remaining(left)),
+        None => PAUSE_LABEL.to_string(),
+    };
+    let _ = items.pause_menu.set_text(label);
+    refresh_status(app);
+}
+
+/// Pause automatic sync from the tray
+fn pause(app: &AppHandle, duration: PauseFor) {
+    crate::pause_auto_sync(app.clone(), duration);
+}
+
+/// Menu id of a direction item
+fn direction_id(direction: SyncDirection) -> &'static str {
+    match direction {
+        SyncDirection::Bidirectional => "direction_bidirectional",
+        SyncDirection::SendOnly => "direction_send_only",
+        SyncDirection::ReceiveOnly => "direction_receive_only",
+    }
+}
+
+/// Check the item of the active direction only
+pub fn set_direction_checked(app: &AppHandle, direction: SyncDirection) {
+    if let Some(items) = app.try_state::<TrayItems>() {
+        for (item_direction, item) in &items.direction {
+            let _ = item.set_checked(*item_direction == direction);
+        }
+    }
+}
+
+/// Switch the global sync direction from the tray
+fn set_direction(app: &AppHandle, direction: SyncDirection) {
+    set_direction_checked(app, direction);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<crate::AppState>();
+        let mut config = state.config.lock().await;
+        config.direction = direction;
+        if let Err(e) = config.save() {
+            log::error!("Failed to save sync direction: {}", e);
+        }
+        log::info!("Sync direction set to {:?}", direction);
+    });
+}
+
 /// Setup the system tray
 pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     // Create menu items
+    let account = MenuItem::with_id(app, "account", "未登录 Not logged in", false, None::<&str>)?;
+    let status = MenuItem::with_id(app, "status", "状态 Status: —", false, None::<&str>)?;
+    let last_pull = MenuItem::with_id(app, "last_pull", "上次接收 Last received: —", false, None::<&str>)?;
+    let last_push = MenuItem::with_id(app, "last_push", "上次发送 Last sent: —", false, None::<&str>)?;
     let auto_sync = CheckMenuItem::with_id(app, "auto_sync", "自动同步 Auto Sync", true, true, None::<&str>)?;
+    let pause_15m = MenuItem::with_id(app, "pause_15m", "15 分钟 15 minutes", true, None::<&str>)?;
+    let pause_1h = MenuItem::with_id(app, "pause_1h", "1 小时 1 hour", true, None::<&str>)?;
+    let pause_tomorrow = MenuItem::with_id(app, "pause_tomorrow", "直到明天 Until tomorrow", true, None::<&str>)?;
+    let resume = MenuItem::with_id(app, "resume", "恢复 Resume", false, None::<&str>)?;
+    let pause_menu = Submenu::with_items(
+        app,
+        PAUSE_LABEL,
+        true,
+        &[&pause_15m, &pause_1h, &pause_tomorrow, &PredefinedMenuItem::separator(app)?, &resume],
+    )?;
     let sync_now = MenuItem::with_id(app, "sync_now", "立即同步 Sync Now", true, None::<&str>)?;
     let show_window = MenuItem::with_id(app, "show_window", "显示窗口 Show Window", true, None::<&str>)?;
     let settings = MenuItem::with_id(app, "settings", "设置 Settings", true, None::<&str>)?;
     let quit = MenuItem::with_id(app, "quit", "退出 Quit", true, None::<&str>)?;
 
+    let (current_direction, repush) = {
+        let state = app.state::<crate::AppState>();
+        let config = state.config.blocking_lock();
+        (config.direction, config.recent.repush)
+    };
+    let recent_repush = CheckMenuItem::with_id(app, "recent_repush", "选中后重新推送 Push again when picked", true, repush, None::<&str>)?;
+    let recent_menu = Submenu::with_items(app, "最近 Recent", true, &[&recent_repush])?;
+    let direction_items = [
+        (SyncDirection::Bidirectional, "双向 Send and Receive"),
+        (SyncDirection::SendOnly, "仅发送 Send Only"),
+        (SyncDirection::ReceiveOnly, "仅接收 Receive Only"),
+    ]
+    .into_iter()
+    .map(|(direction, label)| {
+        CheckMenuItem::with_id(app, direction_id(direction), label, true, direction == current_direction, None::<&str>)
+            .map(|item| (direction, item))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+    let direction_menu = Submenu::with_items(
+        app,
+        "同步方向 Direction",
+        true,
+        &[&direction_items[0].1, &direction_items[1].1, &direction_items[2].1],
+    )?;
+    app.manage(TrayItems {
+        auto_sync: auto_sync.clone(),
+        pause_menu: pause_menu.clone(),
+        resume,
+        direction: direction_items,
+        account: account.clone(),
+        status: status.clone(),
+        last_pull: last_pull.clone(),
+        last_push: last_push.clone(),
+        recent_menu: recent_menu.clone(),
+        recent_repush,
+        problem: Mutex::new(None),
+        shown: Mutex::new(None),
+    });
+
     let menu = Menu::with_items(
         app,
         &[
+            &account,
+            &status,
+            &last_pull,
+            &last_push,
+            &PredefinedMenuItem::separator(app)?,
             &auto_sync,
+            &pause_menu,
+            &direction_menu,
             &PredefinedMenuItem::separator(app)?,
+            &recent_menu,
             &sync_now,
             &show_window,
             &PredefinedMenuItem::separator(app)?,
@@ -32,7 +425,7 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     )?;
 
     let _tray = TrayIconBuilder::with_id("main")
-        .tooltip("GCopy - 剪贴板同步")
+        .tooltip(TOOLTIP)
         .menu(&menu)
         .show_menu_on_left_click(false)
         .on_menu_event(move |app, event| match event.id.as_ref() {
@@ -40,6 +433,10 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                 let new_state = crate::toggle_auto_sync(app.clone());
                 log::info!("Auto sync toggled: {}", new_state);
             }
+            "pause_15m" => pause(app, PauseFor::Minutes(15)),
+            "pause_1h" => pause(app, PauseFor::Minutes(60)),
+            "pause_tomorrow" => pause(app, PauseFor::UntilTomorrow),
+            "resume" => crate::resume_auto_sync(app.clone()),
             "sync_now" => {
                 let app_clone = app.clone();
                 tauri::async_runtime::spawn(async move {
@@ -48,6 +445,9 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                     }
                 });
             }
+            "direction_bidirectional" => set_direction(app, SyncDirection::Bidirectional),
+            "direction_send_only" => set_direction(app, SyncDirection::SendOnly),
+            "direction_receive_only" => set_direction(app, SyncDirection::ReceiveOnly),
             "show_window" => {
                 if let Some(window) = app.get_webview_window("main") {
                     let _ = window.show();
@@ -64,7 +464,12 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            "recent_repush" => set_recent_repush(app),
+            id => {
+                if let Some(id) = id.strip_prefix("recent_").and_then(|id| id.parse().ok()) {
+                    restore_recent(app, id);
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
@@ -86,6 +491,8 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         })
         .build(app)?;
 
+    refresh_status(app);
+    refresh_recent(app);
     log::info!("System tray initialized");
 
     Ok(())
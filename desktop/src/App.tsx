@@ -3,7 +3,8 @@ import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
 import SyncClipboard from './components/SyncClipboard';
 import Settings from './components/Settings';
-import Navbar from './components/Navbar';
+import Navbar, { type ConnectionState } from './components/Navbar';
+import ConflictDialog from './components/ConflictDialog';
 
 type View = 'main' | 'settings';
 
@@ -11,8 +12,10 @@ function App() {
   const [view, setView] = useState<View>('main');
   const [syncStatus, setSyncStatus] = useState({
     autoSyncEnabled: true,
+    pausedRemainingSecs: null as number | null,
     isSyncing: false,
     lastServerIndex: 0,
+    connection: 'online' as ConnectionState,
   });
 
   useEffect(() => {
@@ -31,6 +34,11 @@ function App() {
       } else if (event.payload.type === 'Completed') {
         setSyncStatus((prev) => ({ ...prev, isSyncing: false }));
         invoke<typeof syncStatus>('get_sync_status').then(setSyncStatus);
+      } else if (
+        event.payload.type === 'ConnectionChanged' ||
+        event.payload.type === 'AutoSyncChanged'
+      ) {
+        invoke<typeof syncStatus>('get_sync_status').then(setSyncStatus);
       }
     });
 
@@ -56,6 +64,8 @@ function App() {
           <Settings onBack={() => setView('main')} />
         )}
       </main>
+
+      <ConflictDialog />
     </div>
   );
 }
@@ -0,0 +1,113 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useTranslation } from 'react-i18next';
+import { errorMessage } from '../lib/error';
+
+interface UserInfo {
+  email: string | null;
+  loggedIn: boolean;
+}
+
+export default function Account() {
+  const { t } = useTranslation();
+  const [user, setUser] = useState<UserInfo | null>(null);
+  const [email, setEmail] = useState('');
+  const [code, setCode] = useState('');
+  const [codeSent, setCodeSent] = useState(false);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    invoke<UserInfo>('get_user').then(setUser);
+  }, []);
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    setError('');
+    try {
+      await action();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleSendCode = () =>
+    run(async () => {
+      await invoke('request_login_code', { email });
+      setCodeSent(true);
+    });
+
+  const handleLogin = () =>
+    run(async () => {
+      setUser(await invoke<UserInfo>('login', { email, code }));
+      setCode('');
+      setCodeSent(false);
+    });
+
+  const handleLogout = () =>
+    run(async () => {
+      await invoke('logout');
+      setUser({ email: null, loggedIn: false });
+    });
+
+  if (user?.loggedIn) {
+    return (
+      <div className="space-y-2">
+        <div className="flex items-center gap-2">
+          <span className="text-sm flex-1 truncate">
+            {t('accountLoggedIn', { email: user.email })}
+          </span>
+          <button className="btn btn-ghost btn-xs" onClick={handleLogout} disabled={busy}>
+            {t('accountLogout')}
+          </button>
+        </div>
+        {error && <p className="text-xs text-error">{error}</p>}
+      </div>
+    );
+  }
+
+  return (
+    <div className="space-y-2">
+      <div className="flex gap-2">
+        <input
+          type="email"
+          className="input input-bordered input-sm w-full"
+          placeholder={t('accountEmail')}
+          value={email}
+          onChange={(e) => setEmail(e.target.value)}
+        />
+        <button
+          className="btn btn-outline btn-sm"
+          onClick={handleSendCode}
+          disabled={busy || !email.trim()}
+        >
+          {t('accountSendCode')}
+        </button>
+      </div>
+
+      {codeSent && (
+        <div className="flex gap-2">
+          <input
+            type="text"
+            inputMode="numeric"
+            className="input input-bordered input-sm w-full"
+            placeholder={t('accountCode')}
+            value={code}
+            onChange={(e) => setCode(e.target.value)}
+          />
+          <button
+            className="btn btn-primary btn-sm"
+            onClick={handleLogin}
+            disabled={busy || code.trim().length !== 6}
+          >
+            {t('accountLogin')}
+          </button>
+        </div>
+      )}
+
+      {error && <p className="text-xs text-error">{error}</p>}
+    </div>
+  );
+}
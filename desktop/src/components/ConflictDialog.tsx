@@ -0,0 +1,104 @@
+import { useState, useEffect, useCallback } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/core';
+import { useTranslation } from 'react-i18next';
+import { errorMessage } from '../lib/error';
+
+interface PendingConflict {
+  index: number;
+  contentType: string;
+  device: string | null;
+  remoteCreatedAt: number | null;
+  localChangedAt: number;
+  preview: string | null;
+}
+
+type ConflictChoice = 'local' | 'remote';
+
+export default function ConflictDialog() {
+  const { t } = useTranslation();
+  const [conflict, setConflict] = useState<PendingConflict | null>(null);
+  const [resolving, setResolving] = useState(false);
+  const [error, setError] = useState('');
+
+  const refresh = useCallback(async () => {
+    setConflict(await invoke<PendingConflict | null>('get_pending_conflict'));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+
+    // A conflict appears on pull and goes away once either side is synced
+    const unlisten = listen<{ type: string }>('sync-event', (event) => {
+      if (['Conflict', 'Pulled', 'Pushed'].includes(event.payload.type)) {
+        refresh();
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [refresh]);
+
+  const handleResolve = async (choice: ConflictChoice) => {
+    setResolving(true);
+    setError('');
+    try {
+      await invoke('resolve_conflict', { choice });
+      await refresh();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setResolving(false);
+    }
+  };
+
+  if (!conflict) {
+    return null;
+  }
+
+  const formatTime = (timestamp: number) => new Date(timestamp).toLocaleTimeString();
+
+  return (
+    <div className="modal modal-open">
+      <div className="modal-box space-y-3">
+        <h3 className="font-bold text-lg">{t('conflictTitle')}</h3>
+        <p className="text-sm">
+          {t('conflictBody', {
+            device: conflict.device ?? t('conflictAnotherDevice'),
+            type: t(conflict.contentType),
+          })}
+        </p>
+        {conflict.preview && (
+          <pre className="text-xs bg-base-200 rounded p-2 whitespace-pre-wrap break-all max-h-32 overflow-auto">
+            {conflict.preview}
+          </pre>
+        )}
+        <div className="text-xs opacity-70 space-y-1">
+          <p>{t('conflictLocalChanged', { time: formatTime(conflict.localChangedAt) })}</p>
+          {conflict.remoteCreatedAt && (
+            <p>{t('conflictRemoteCreated', { time: formatTime(conflict.remoteCreatedAt) })}</p>
+          )}
+        </div>
+        <p className="text-xs opacity-70">{t('conflictTimeoutHint')}</p>
+        {error && <p className="text-sm text-error">{error}</p>}
+        <div className="modal-action">
+          <button
+            className="btn btn-outline btn-sm"
+            onClick={() => handleResolve('local')}
+            disabled={resolving}
+          >
+            {t('conflictKeepLocal')}
+          </button>
+          <button
+            className="btn btn-primary btn-sm"
+            onClick={() => handleResolve('remote')}
+            disabled={resolving}
+          >
+            {t('conflictKeepRemote')}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
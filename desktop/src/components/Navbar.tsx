@@ -2,16 +2,32 @@ import { useTranslation } from 'react-i18next';
 import { Cog6ToothIcon, ArrowLeftIcon } from '@heroicons/react/24/outline';
 import Logo from './Logo';
 
+export type ConnectionState =
+  | 'online'
+  | 'degraded'
+  | 'offline'
+  | 'unauthorized'
+  | 'certificateMismatch';
+
 interface NavbarProps {
   onSettingsClick: () => void;
   onBackClick: () => void;
   showBack: boolean;
   syncStatus: {
     autoSyncEnabled: boolean;
+    pausedRemainingSecs?: number | null;
     isSyncing: boolean;
+    connection?: ConnectionState;
   };
 }
 
+const connectionBadges = {
+  degraded: { className: 'badge-warning', label: 'connectionDegraded' },
+  offline: { className: 'badge-error', label: 'connectionOffline' },
+  unauthorized: { className: 'badge-error', label: 'connectionUnauthorized' },
+  certificateMismatch: { className: 'badge-error', label: 'connectionCertificateMismatch' },
+} as const;
+
 export default function Navbar({
   onSettingsClick,
   onBackClick,
@@ -39,6 +55,20 @@ export default function Navbar({
         )}
       </div>
       <div className="flex-none">
+        {syncStatus.connection && syncStatus.connection !== 'online' && (
+          <span
+            className={`badge badge-sm mr-2 ${connectionBadges[syncStatus.connection].className}`}
+          >
+            {t(connectionBadges[syncStatus.connection].label)}
+          </span>
+        )}
+        {syncStatus.pausedRemainingSecs != null && (
+          <span className="badge badge-ghost badge-sm mr-2">
+            {t('paused', {
+              count: Math.ceil(syncStatus.pausedRemainingSecs / 60),
+            })}
+          </span>
+        )}
         {syncStatus.autoSyncEnabled && (
           <span className="badge badge-success badge-sm mr-2">
             {t('autoSync')}
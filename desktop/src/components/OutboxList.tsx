@@ -0,0 +1,85 @@
+import { useState, useEffect, useCallback } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/core';
+import { useTranslation } from 'react-i18next';
+import { ArrowUpTrayIcon, XMarkIcon } from '@heroicons/react/24/outline';
+import { formatSize } from '../lib/format';
+
+interface OutboxItem {
+  id: number;
+  contentType: string;
+  size: number;
+  createdAt: number;
+}
+
+export default function OutboxList() {
+  const { t } = useTranslation();
+  const [items, setItems] = useState<OutboxItem[]>([]);
+
+  const refresh = useCallback(async () => {
+    setItems(await invoke<OutboxItem[]>('get_outbox'));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+
+    const unlisten = listen<{ type: string }>('sync-event', (event) => {
+      if (event.payload.type === 'OutboxChanged') {
+        refresh();
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [refresh]);
+
+  const handleCancel = async (id: number) => {
+    await invoke('cancel_outbox_item', { id });
+    await refresh();
+  };
+
+  const handleClear = async () => {
+    await invoke('clear_outbox');
+    await refresh();
+  };
+
+  if (items.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="card bg-base-100 shadow-sm">
+      <div className="card-body p-4">
+        <div className="flex items-center justify-between">
+          <h3 className="card-title text-sm flex items-center gap-2">
+            <ArrowUpTrayIcon className="w-4 h-4" />
+            {t('outbox', { count: items.length })}
+          </h3>
+          <button className="btn btn-ghost btn-xs" onClick={handleClear}>
+            {t('outboxClear')}
+          </button>
+        </div>
+        <p className="text-xs opacity-70">{t('outboxHint')}</p>
+        <ul className="space-y-1">
+          {items.map((item) => (
+            <li key={item.id} className="flex items-center gap-2 text-sm">
+              <span className="flex-1 min-w-0 truncate">{t(item.contentType)}</span>
+              <span className="text-xs opacity-70">{formatSize(item.size)}</span>
+              <span className="text-xs opacity-70">
+                {new Date(item.createdAt * 1000).toLocaleTimeString()}
+              </span>
+              <button
+                className="btn btn-ghost btn-xs btn-circle"
+                title={t('cancel')}
+                onClick={() => handleCancel(item.id)}
+              >
+                <XMarkIcon className="w-4 h-4" />
+              </button>
+            </li>
+          ))}
+        </ul>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,134 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useTranslation } from 'react-i18next';
+import { errorMessage } from '../lib/error';
+
+interface PairingInfo {
+  account: string;
+  keyId: string;
+  code: string;
+  qrSvg: string;
+}
+
+interface PairingStatus {
+  account: string;
+  paired: boolean;
+  keyId: string | null;
+  retiredKeyIds: string[];
+}
+
+export default function Pairing() {
+  const { t } = useTranslation();
+  const [status, setStatus] = useState<PairingStatus | null>(null);
+  const [pairing, setPairing] = useState<PairingInfo | null>(null);
+  const [code, setCode] = useState('');
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState('');
+
+  const refreshStatus = async () => {
+    try {
+      setStatus(await invoke<PairingStatus>('get_pairing_status'));
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  };
+
+  useEffect(() => {
+    refreshStatus();
+  }, []);
+
+  const run = async (action: () => Promise<PairingInfo | null>) => {
+    setBusy(true);
+    setError('');
+    try {
+      setPairing(await action());
+      await refreshStatus();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleShow = () => run(() => invoke<PairingInfo>('create_pairing'));
+
+  const handleImport = () =>
+    run(async () => {
+      const info = await invoke<PairingInfo>('import_pairing', { code });
+      setCode('');
+      return info;
+    });
+
+  const handleRotate = () => {
+    if (!window.confirm(t('pairingRotateConfirm'))) return;
+    run(() => invoke<PairingInfo>('rotate_pairing_key'));
+  };
+
+  const handleUnpair = () => {
+    if (!window.confirm(t('pairingUnpairConfirm'))) return;
+    run(async () => {
+      await invoke('unpair');
+      return null;
+    });
+  };
+
+  return (
+    <div className="space-y-2">
+      {status && (
+        <p className="text-xs opacity-70 break-all">
+          {status.paired
+            ? t('pairingPaired', { keyId: status.keyId, account: status.account })
+            : t('pairingNotPaired', { account: status.account })}
+        </p>
+      )}
+
+      {pairing ? (
+        <div className="flex flex-col items-center gap-2">
+          <div
+            className="bg-white p-2 rounded"
+            dangerouslySetInnerHTML={{ __html: pairing.qrSvg }}
+          />
+          <code className="text-xs break-all select-all text-center">{pairing.code}</code>
+          <p className="text-xs opacity-70">{t('pairingShowHint')}</p>
+          <button className="btn btn-ghost btn-xs" onClick={() => setPairing(null)}>
+            {t('pairingHide')}
+          </button>
+        </div>
+      ) : (
+        <button className="btn btn-outline btn-sm w-full" onClick={handleShow} disabled={busy}>
+          {t('pairingShow')}
+        </button>
+      )}
+
+      <div className="flex gap-2">
+        <input
+          type="text"
+          className="input input-bordered input-sm w-full"
+          placeholder={t('pairingCodePlaceholder')}
+          value={code}
+          onChange={(e) => setCode(e.target.value)}
+        />
+        <button
+          className="btn btn-outline btn-sm"
+          onClick={handleImport}
+          disabled={busy || !code.trim()}
+        >
+          {t('pairingImport')}
+        </button>
+      </div>
+
+      {status?.paired && (
+        <div className="flex gap-2">
+          <button className="btn btn-ghost btn-xs" onClick={handleRotate} disabled={busy}>
+            {t('pairingRotate')}
+          </button>
+          <button className="btn btn-ghost btn-xs" onClick={handleUnpair} disabled={busy}>
+            {t('pairingUnpair')}
+          </button>
+        </div>
+      )}
+
+      {error && <p className="text-xs text-error">{error}</p>}
+    </div>
+  );
+}
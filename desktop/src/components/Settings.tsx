@@ -2,6 +2,9 @@ import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { enable, disable, isEnabled } from '@tauri-apps/plugin-autostart';
 import { useTranslation } from 'react-i18next';
+import { errorMessage } from '../lib/error';
+import Account from './Account';
+import Pairing from './Pairing';
 
 interface AppConfig {
   serverUrl: string;
@@ -17,6 +20,84 @@ interface AppConfig {
     manualSync: string;
     toggleWindow: string;
   };
+  direction: SyncDirection;
+  conflictPolicy: ConflictPolicy;
+  compression: {
+    algorithm: CompressionAlgorithm;
+    minSize: number;
+  };
+  network: NetworkConfig;
+  serverAccess: ServerAccess[];
+  deviceName: string;
+  notifications: NotificationConfig;
+  recent: {
+    maxItems: number;
+    repush: boolean;
+  };
+}
+
+interface NotificationConfig {
+  received: boolean;
+  pushFailed: boolean;
+  authExpired: boolean;
+  tooLarge: boolean;
+  conflict: boolean;
+  previews: boolean;
+  doNotDisturb: boolean;
+}
+
+const NOTIFICATION_TOGGLES: { key: keyof NotificationConfig; label: string }[] = [
+  { key: 'received', label: 'notifyReceived' },
+  { key: 'previews', label: 'notifyPreviews' },
+  { key: 'pushFailed', label: 'notifyPushFailed' },
+  { key: 'authExpired', label: 'notifyAuthExpired' },
+  { key: 'tooLarge', label: 'notifyTooLarge' },
+  { key: 'conflict', label: 'notifyConflict' },
+];
+
+interface ServerAccess {
+  serverUrl: string;
+  headers: { name: string; value: string }[];
+  username: string;
+  password: string;
+}
+
+type CertificateTrust = 'system' | 'trustOnFirstUse';
+
+interface CertificatePin {
+  host: string;
+  sha256: string;
+  pinnedAt: number;
+}
+
+interface NetworkConfig {
+  proxy: string;
+  noProxy: string;
+  certificateTrust: CertificateTrust;
+  caCertificates: string[];
+  clientCertificate: string;
+  clientKey: string;
+  connectTimeoutSecs: number;
+  readTimeoutSecs: number;
+}
+
+type SyncDirection = 'bidirectional' | 'sendOnly' | 'receiveOnly';
+
+type ConflictPolicy = 'preferLocal' | 'preferRemote' | 'newest' | 'ask';
+
+type CompressionAlgorithm = 'off' | 'zstd' | 'gzip';
+
+interface ServerCheck {
+  reachable: boolean;
+  latencyMs: number | null;
+  tls: { certificateSha256: string | null } | null;
+  version: string | null;
+  compatible: boolean | null;
+  maxContentLength: number | null;
+  loggedIn: boolean;
+  account: string | null;
+  warnings: string[];
+  error: { message: string } | null;
 }
 
 interface SettingsProps {
@@ -28,11 +109,16 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
   const [config, setConfig] = useState<AppConfig | null>(null);
   const [saving, setSaving] = useState(false);
   const [saved, setSaved] = useState(false);
+  const [saveError, setSaveError] = useState('');
   const [autostartEnabled, setAutostartEnabled] = useState(false);
+  const [checking, setChecking] = useState(false);
+  const [serverCheck, setServerCheck] = useState<ServerCheck | null>(null);
+  const [pins, setPins] = useState<CertificatePin[]>([]);
 
   useEffect(() => {
     // Load config
     invoke<AppConfig>('get_config').then(setConfig);
+    invoke<CertificatePin[]>('get_certificate_pins').then(setPins);
 
     // Check autostart status
     isEnabled().then(setAutostartEnabled);
@@ -42,6 +128,7 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
     if (!config) return;
 
     setSaving(true);
+    setSaveError('');
     try {
       await invoke('save_config', { config });
 
@@ -58,11 +145,50 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
       setTimeout(() => setSaved(false), 2000);
     } catch (e) {
       console.error('Failed to save config:', e);
+      setSaveError(errorMessage(e));
     } finally {
       setSaving(false);
     }
   };
 
+  const setNetwork = (network: Partial<NetworkConfig>) => {
+    if (!config) return;
+    setConfig({ ...config, network: { ...config.network, ...network } });
+  };
+
+  const access: ServerAccess = config?.serverAccess.find(
+    (a) => a.serverUrl === config.serverUrl
+  ) ?? { serverUrl: config?.serverUrl ?? '', headers: [], username: '', password: '' };
+
+  const setAccess = (update: Partial<ServerAccess>) => {
+    if (!config) return;
+    const others = config.serverAccess.filter((a) => a.serverUrl !== config.serverUrl);
+    setConfig({ ...config, serverAccess: [...others, { ...access, ...update }] });
+  };
+
+  const setHeader = (index: number, header: { name?: string; value?: string }) => {
+    setAccess({
+      headers: access.headers.map((h, i) => (i === index ? { ...h, ...header } : h)),
+    });
+  };
+
+  const handleResetPins = async (host?: string) => {
+    await invoke('reset_certificate_pins', { host });
+    setPins(await invoke<CertificatePin[]>('get_certificate_pins'));
+  };
+
+  const handleCheckServer = async () => {
+    if (!config) return;
+
+    setChecking(true);
+    setServerCheck(null);
+    try {
+      setServerCheck(await invoke<ServerCheck>('check_server', { url: config.serverUrl }));
+    } finally {
+      setChecking(false);
+    }
+  };
+
   if (!config) {
     return (
       <div className="flex justify-center py-8">
@@ -78,13 +204,76 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
         <label className="label">
           <span className="label-text">{t('serverUrl')}</span>
         </label>
+        <div className="flex gap-2">
+          <input
+            type="url"
+            className="input input-bordered w-full"
+            value={config.serverUrl}
+            onChange={(e) => {
+              setConfig({ ...config, serverUrl: e.target.value });
+              setServerCheck(null);
+            }}
+          />
+          <button
+            className="btn btn-outline"
+            onClick={handleCheckServer}
+            disabled={checking}
+          >
+            {checking ? (
+              <span className="loading loading-spinner loading-sm"></span>
+            ) : (
+              t('checkServer')
+            )}
+          </button>
+        </div>
+        {serverCheck && (
+          <div className="text-sm mt-2 space-y-1">
+            {serverCheck.reachable ? (
+              <p className="text-success">
+                {t('serverReachable', { latency: serverCheck.latencyMs ?? 0 })}
+                {serverCheck.version && ` · ${serverCheck.version}`}
+                {serverCheck.maxContentLength &&
+                  ` · ${t('serverLimit', {
+                    size: Math.round(serverCheck.maxContentLength / 1024 / 1024),
+                  })}`}
+              </p>
+            ) : (
+              <p className="text-error">
+                {t('serverUnreachable', { error: serverCheck.error?.message ?? '' })}
+              </p>
+            )}
+            {serverCheck.tls?.certificateSha256 && (
+              <p className="opacity-70 break-all">
+                {t('serverCertificate')}: {serverCheck.tls.certificateSha256}
+              </p>
+            )}
+            {serverCheck.reachable && (
+              <p className="opacity-70">
+                {serverCheck.loggedIn
+                  ? t('serverLoggedIn', { account: serverCheck.account ?? '' })
+                  : t('serverNotLoggedIn')}
+              </p>
+            )}
+            {serverCheck.warnings.map((warning) => (
+              <p key={warning} className="text-warning">
+                {warning}
+              </p>
+            ))}
+          </div>
+        )}
+      </div>
+
+      {/* Device Name */}
+      <div className="form-control">
+        <label className="label">
+          <span className="label-text">{t('deviceName')}</span>
+        </label>
         <input
-          type="url"
+          type="text"
           className="input input-bordered w-full"
-          value={config.serverUrl}
-          onChange={(e) =>
-            setConfig({ ...config, serverUrl: e.target.value })
-          }
+          placeholder={t('deviceNamePlaceholder')}
+          value={config.deviceName}
+          onChange={(e) => setConfig({ ...config, deviceName: e.target.value })}
         />
       </div>
 
@@ -135,6 +324,67 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
         </label>
       </div>
 
+      {/* Sync Direction */}
+      <div className="form-control">
+        <label className="label">
+          <span className="label-text">{t('syncDirection')}</span>
+        </label>
+        <select
+          className="select select-bordered w-full"
+          value={config.direction}
+          onChange={(e) =>
+            setConfig({ ...config, direction: e.target.value as SyncDirection })
+          }
+        >
+          <option value="bidirectional">{t('directionBidirectional')}</option>
+          <option value="sendOnly">{t('directionSendOnly')}</option>
+          <option value="receiveOnly">{t('directionReceiveOnly')}</option>
+        </select>
+      </div>
+
+      {/* Conflict Policy */}
+      <div className="form-control">
+        <label className="label">
+          <span className="label-text">{t('conflictPolicy')}</span>
+        </label>
+        <select
+          className="select select-bordered w-full"
+          value={config.conflictPolicy}
+          onChange={(e) =>
+            setConfig({ ...config, conflictPolicy: e.target.value as ConflictPolicy })
+          }
+        >
+          <option value="newest">{t('conflictNewest')}</option>
+          <option value="preferLocal">{t('conflictPreferLocal')}</option>
+          <option value="preferRemote">{t('conflictPreferRemote')}</option>
+          <option value="ask">{t('conflictAsk')}</option>
+        </select>
+      </div>
+
+      {/* Compression */}
+      <div className="form-control">
+        <label className="label">
+          <span className="label-text">{t('compression')}</span>
+        </label>
+        <select
+          className="select select-bordered w-full"
+          value={config.compression.algorithm}
+          onChange={(e) =>
+            setConfig({
+              ...config,
+              compression: {
+                ...config.compression,
+                algorithm: e.target.value as CompressionAlgorithm,
+              },
+            })
+          }
+        >
+          <option value="off">{t('compressionOff')}</option>
+          <option value="zstd">zstd</option>
+          <option value="gzip">gzip</option>
+        </select>
+      </div>
+
       {/* Sync Types */}
       <div className="form-control">
         <label className="label">
@@ -186,6 +436,269 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
         </div>
       </div>
 
+      {/* Notifications */}
+      <div className="form-control">
+        <label className="label cursor-pointer">
+          <span className="label-text">{t('notifications')}</span>
+          <span className="flex items-center gap-2">
+            <span className="label-text">{t('doNotDisturb')}</span>
+            <input
+              type="checkbox"
+              className="toggle toggle-sm"
+              checked={config.notifications.doNotDisturb}
+              onChange={(e) =>
+                setConfig({
+                  ...config,
+                  notifications: { ...config.notifications, doNotDisturb: e.target.checked },
+                })
+              }
+            />
+          </span>
+        </label>
+        <div className="space-y-2 pl-2">
+          {NOTIFICATION_TOGGLES.map(({ key, label }) => (
+            <label key={key} className="label cursor-pointer justify-start gap-3">
+              <input
+                type="checkbox"
+                className="checkbox checkbox-sm"
+                checked={config.notifications[key]}
+                disabled={config.notifications.doNotDisturb}
+                onChange={(e) =>
+                  setConfig({
+                    ...config,
+                    notifications: { ...config.notifications, [key]: e.target.checked },
+                  })
+                }
+              />
+              <span className="label-text">{t(label)}</span>
+            </label>
+          ))}
+        </div>
+      </div>
+
+      {/* Recent items in the tray */}
+      <div className="form-control">
+        <label className="label">
+          <span className="label-text">{t('recentItems')}</span>
+        </label>
+        <input
+          type="number"
+          min="0"
+          max="50"
+          className="input input-bordered w-full"
+          value={config.recent.maxItems}
+          onChange={(e) =>
+            setConfig({
+              ...config,
+              recent: { ...config.recent, maxItems: Math.max(0, parseInt(e.target.value) || 0) },
+            })
+          }
+        />
+        <label className="label cursor-pointer justify-start gap-3">
+          <input
+            type="checkbox"
+            className="checkbox checkbox-sm"
+            checked={config.recent.repush}
+            onChange={(e) =>
+              setConfig({ ...config, recent: { ...config.recent, repush: e.target.checked } })
+            }
+          />
+          <span className="label-text">{t('recentRepush')}</span>
+        </label>
+      </div>
+
+      {/* Account */}
+      <div className="collapse collapse-arrow border border-base-300">
+        <input type="checkbox" />
+        <div className="collapse-title label-text">{t('account')}</div>
+        <div className="collapse-content">
+          <Account />
+        </div>
+      </div>
+
+      {/* Device pairing */}
+      <div className="collapse collapse-arrow border border-base-300">
+        <input type="checkbox" />
+        <div className="collapse-title label-text">{t('pairing')}</div>
+        <div className="collapse-content">
+          <Pairing />
+        </div>
+      </div>
+
+      {/* Network */}
+      <div className="collapse collapse-arrow border border-base-300">
+        <input type="checkbox" />
+        <div className="collapse-title label-text">{t('network')}</div>
+        <div className="collapse-content space-y-2">
+          <input
+            type="text"
+            className="input input-bordered input-sm w-full"
+            placeholder={t('proxyPlaceholder')}
+            value={config.network.proxy}
+            onChange={(e) =>
+              setNetwork({ proxy: e.target.value })
+            }
+          />
+          <input
+            type="text"
+            className="input input-bordered input-sm w-full"
+            placeholder={t('noProxyPlaceholder')}
+            value={config.network.noProxy}
+            onChange={(e) =>
+              setNetwork({ noProxy: e.target.value })
+            }
+          />
+          <select
+            className="select select-bordered select-sm w-full"
+            value={config.network.certificateTrust}
+            onChange={(e) =>
+              setNetwork({ certificateTrust: e.target.value as CertificateTrust })
+            }
+          >
+            <option value="system">{t('trustSystem')}</option>
+            <option value="trustOnFirstUse">{t('trustOnFirstUse')}</option>
+          </select>
+          {pins.length > 0 && (
+            <div className="text-xs space-y-1">
+              <div className="flex items-center justify-between">
+                <span className="label-text text-xs">{t('pinnedCertificates')}</span>
+                <button className="btn btn-ghost btn-xs" onClick={() => handleResetPins()}>
+                  {t('resetAll')}
+                </button>
+              </div>
+              {pins.map((pin) => (
+                <div key={pin.host} className="flex items-center gap-2">
+                  <div className="flex-1 min-w-0">
+                    <div>{pin.host}</div>
+                    <div className="opacity-70 truncate" title={pin.sha256}>
+                      {pin.sha256}
+                    </div>
+                  </div>
+                  <button
+                    className="btn btn-ghost btn-xs"
+                    onClick={() => handleResetPins(pin.host)}
+                  >
+                    {t('reset')}
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
+          <textarea
+            className="textarea textarea-bordered textarea-sm w-full"
+            placeholder={t('caCertificatesPlaceholder')}
+            value={config.network.caCertificates.join('\n')}
+            onChange={(e) =>
+              setNetwork({
+                caCertificates: e.target.value.split('\n').map((line) => line.trim()),
+              })
+            }
+          />
+          <input
+            type="text"
+            className="input input-bordered input-sm w-full"
+            placeholder={t('clientCertificatePlaceholder')}
+            value={config.network.clientCertificate}
+            onChange={(e) =>
+              setNetwork({ clientCertificate: e.target.value })
+            }
+          />
+          <input
+            type="text"
+            className="input input-bordered input-sm w-full"
+            placeholder={t('clientKeyPlaceholder')}
+            value={config.network.clientKey}
+            onChange={(e) =>
+              setNetwork({ clientKey: e.target.value })
+            }
+          />
+          <div className="flex gap-2">
+            <label className="form-control w-full">
+              <span className="label-text text-xs">{t('connectTimeout')}</span>
+              <input
+                type="number"
+                min="1"
+                className="input input-bordered input-sm w-full"
+                value={config.network.connectTimeoutSecs}
+                onChange={(e) =>
+                  setNetwork({ connectTimeoutSecs: parseInt(e.target.value) || 10 })
+                }
+              />
+            </label>
+            <label className="form-control w-full">
+              <span className="label-text text-xs">{t('readTimeout')}</span>
+              <input
+                type="number"
+                min="0"
+                className="input input-bordered input-sm w-full"
+                value={config.network.readTimeoutSecs}
+                onChange={(e) =>
+                  setNetwork({ readTimeoutSecs: parseInt(e.target.value) || 0 })
+                }
+              />
+            </label>
+          </div>
+        </div>
+      </div>
+
+      {/* Reverse proxy access for the current server */}
+      <div className="collapse collapse-arrow border border-base-300">
+        <input type="checkbox" />
+        <div className="collapse-title label-text">{t('serverAccess')}</div>
+        <div className="collapse-content space-y-2">
+          <div className="flex gap-2">
+            <input
+              type="text"
+              className="input input-bordered input-sm w-full"
+              placeholder={t('username')}
+              value={access.username}
+              onChange={(e) => setAccess({ username: e.target.value })}
+            />
+            <input
+              type="password"
+              className="input input-bordered input-sm w-full"
+              placeholder={t('password')}
+              value={access.password}
+              onChange={(e) => setAccess({ password: e.target.value })}
+            />
+          </div>
+          {access.headers.map((header, index) => (
+            <div key={index} className="flex gap-2">
+              <input
+                type="text"
+                className="input input-bordered input-sm w-full"
+                placeholder={t('headerName')}
+                value={header.name}
+                onChange={(e) => setHeader(index, { name: e.target.value })}
+              />
+              <input
+                type="password"
+                className="input input-bordered input-sm w-full"
+                placeholder={t('headerValue')}
+                value={header.value}
+                onChange={(e) => setHeader(index, { value: e.target.value })}
+              />
+              <button
+                className="btn btn-ghost btn-sm"
+                onClick={() =>
+                  setAccess({ headers: access.headers.filter((_, i) => i !== index) })
+                }
+              >
+                ✕
+              </button>
+            </div>
+          ))}
+          <button
+            className="btn btn-ghost btn-xs"
+            onClick={() =>
+              setAccess({ headers: [...access.headers, { name: '', value: '' }] })
+            }
+          >
+            {t('addHeader')}
+          </button>
+        </div>
+      </div>
+
       {/* Save Button */}
       <button
         className={`btn btn-primary w-full ${saving ? 'loading' : ''}`}
@@ -198,6 +711,12 @@ export default function Settings({ onBack: _onBack }: SettingsProps) {
         {t('save')}
       </button>
 
+      {saveError && (
+        <div className="alert alert-error">
+          <span>{saveError}</span>
+        </div>
+      )}
+
       {saved && (
         <div className="alert alert-success">
           <span>{t('saved')}</span>
@@ -1,6 +1,8 @@
 import { useState, useEffect, useCallback } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '../lib/error';
+import { formatSize } from '../lib/format';
 import { useTranslation } from 'react-i18next';
 import { useLiveQuery } from 'dexie-react-hooks';
 import {
@@ -10,6 +12,13 @@ import {
 import { db, addHistoryItem } from '../models/db';
 import { ClipboardContent } from '../lib/clipboard';
 import HistoryList from './HistoryList';
+import OutboxList from './OutboxList';
+
+interface TransferProgress {
+  id: number;
+  bytes: number;
+  total: number | null;
+}
 
 interface SyncClipboardProps {
   syncStatus: {
@@ -23,6 +32,7 @@ export default function SyncClipboard({ syncStatus }: SyncClipboardProps) {
   const { t } = useTranslation();
   const [status, setStatus] = useState<'idle' | 'syncing' | 'success' | 'error'>('idle');
   const [message, setMessage] = useState('');
+  const [progress, setProgress] = useState<TransferProgress | null>(null);
 
   // Live query for history
   const history = useLiveQuery(() =>
@@ -70,7 +80,17 @@ export default function SyncClipboard({ syncStatus }: SyncClipboardProps) {
 
   // Handle sync events
   useEffect(() => {
-    const unlisten = listen<{ type: string; content_type?: string; message?: string }>(
+    const unlisten = listen<{
+      type: string;
+      content_type?: string;
+      message?: string;
+      id?: number;
+      bytes?: number;
+      total?: number | null;
+      size?: number;
+      limit?: number | null;
+      device?: string | null;
+    }>(
       'sync-event',
       (event) => {
         const { type, content_type, message: errorMsg } = event.payload;
@@ -84,15 +104,41 @@ export default function SyncClipboard({ syncStatus }: SyncClipboardProps) {
             setStatus('success');
             setMessage(`${t('pushed')} (${content_type})`);
             break;
-          case 'Pulled':
+          case 'Pulled': {
+            const { device } = event.payload;
+            setStatus('success');
+            setMessage(
+              device
+                ? t('pulledFrom', { type: content_type, device })
+                : `${t('pulled')} (${content_type})`
+            );
+            break;
+          }
+          case 'Unchanged':
             setStatus('success');
-            setMessage(`${t('pulled')} (${content_type})`);
+            setMessage(`${t('unchanged')} (${content_type})`);
             break;
           case 'Error':
             setStatus('error');
             setMessage(errorMsg || t('error'));
             break;
+          case 'TooLarge': {
+            const { size = 0, limit = null } = event.payload;
+            setStatus('error');
+            setMessage(
+              limit
+                ? t('tooLarge', { size: formatSize(size), limit: formatSize(limit) })
+                : t('tooLargeNoLimit', { size: formatSize(size) })
+            );
+            break;
+          }
+          case 'Progress': {
+            const { id = 0, bytes = 0, total = null } = event.payload;
+            setProgress(total !== null && bytes >= total ? null : { id, bytes, total });
+            break;
+          }
           case 'Completed':
+            setProgress(null);
             setTimeout(() => {
               setStatus('idle');
               setMessage('');
@@ -107,12 +153,18 @@ export default function SyncClipboard({ syncStatus }: SyncClipboardProps) {
     };
   }, [t]);
 
+  const handleCancelTransfer = useCallback(async () => {
+    if (!progress) return;
+    await invoke('cancel_transfer', { id: progress.id });
+    setProgress(null);
+  }, [progress]);
+
   const handleSyncNow = useCallback(async () => {
     try {
       await invoke('sync_now');
     } catch (e) {
       setStatus('error');
-      setMessage(String(e));
+      setMessage(errorMessage(e));
     }
   }, []);
 
@@ -136,6 +188,24 @@ export default function SyncClipboard({ syncStatus }: SyncClipboardProps) {
             {syncStatus.isSyncing ? t('syncing') : t('syncNow')}
           </button>
 
+          {/* Transfer progress */}
+          {progress && (
+            <div className="flex items-center gap-2 mt-2">
+              {progress.total ? (
+                <progress
+                  className="progress progress-primary flex-1"
+                  value={progress.bytes}
+                  max={progress.total}
+                ></progress>
+              ) : (
+                <progress className="progress progress-primary flex-1"></progress>
+              )}
+              <button className="btn btn-ghost btn-xs" onClick={handleCancelTransfer}>
+                {t('cancel')}
+              </button>
+            </div>
+          )}
+
           {/* Status message */}
           {message && (
             <div
@@ -153,6 +223,9 @@ export default function SyncClipboard({ syncStatus }: SyncClipboardProps) {
         </div>
       </div>
 
+      {/* Pushes waiting for the server */}
+      <OutboxList />
+
       {/* Current Clipboard */}
       <div className="card bg-base-100 shadow-sm">
         <div className="card-body p-4">
@@ -0,0 +1,13 @@
+export interface AppError {
+  code: string;
+  message: string;
+  details?: Record<string, unknown> | null;
+}
+
+export function isAppError(e: unknown): e is AppError {
+  return typeof e === 'object' && e !== null && 'code' in e && 'message' in e;
+}
+
+export function errorMessage(e: unknown): string {
+  return isAppError(e) ? e.message : String(e);
+}
@@ -129,12 +129,16 @@ export default function SyncClipboard() {
       await pushClipboard();
       return;
     }
-    const xclientname = response.headers.get("x-clientname");
+    // Desktop clients send their device name percent-encoded
+    let xclientname = response.headers.get("x-clientname") || "UNKNOWN";
+    try {
+      xclientname = decodeURIComponent(xclientname);
+    } catch {}
     addLog({
       message: t("logs.received", {
         type: t(xtype),
         index: xindex,
-        clientname: xclientname ?? "UNKNOWN",
+        clientname: xclientname,
       }),
     });
 
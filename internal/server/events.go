@@ -0,0 +1,61 @@
+package server
+
+import (
+	"fmt"
+	"net/http"
+	"strconv"
+	"time"
+
+	"github.com/gin-gonic/gin"
+)
+
+// keepaliveInterval keeps idle event streams open behind proxies
+const keepaliveInterval = 30 * time.Second
+
+// clipboardEventsHandler streams the index of every new clipboard as
+// Server-Sent Events, so clients can pull right away instead of polling.
+func (s *Server) clipboardEventsHandler(c *gin.Context) {
+	subject, ok := c.Get("subject")
+	if !ok {
+		c.JSON(http.StatusNotFound, gin.H{"message": "Subject not found"})
+		return
+	}
+	sub, ok := subject.(string)
+	if !ok {
+		c.JSON(http.StatusInternalServerError, gin.H{"message": "Subject type assert failed"})
+		return
+	}
+
+	events, unsubscribe := s.wall.Subscribe(sub)
+	defer unsubscribe()
+
+	c.Header("Content-Type", "text/event-stream")
+	c.Header("Cache-Control", "no-cache")
+	c.Header("X-Accel-Buffering", "no")
+
+	// Announce the current index so clients catch up on connect
+	index := 0
+	if cb := s.wall.Get(sub); cb != nil {
+		index = cb.Index
+	}
+	c.SSEvent("index", strconv.Itoa(index))
+	c.Writer.Flush()
+
+	keepalive := time.NewTicker(keepaliveInterval)
+	defer keepalive.Stop()
+
+	for {
+		select {
+		case <-c.Request.Context().Done():
+			return
+		case index := <-events:
+			c.SSEvent("index", strconv.Itoa(index))
+			c.Writer.Flush()
+		case <-keepalive.C:
+			if _, err := fmt.Fprint(c.Writer, ": keepalive\n\n"); err != nil {
+				return
+			}
+			c.Writer.Flush()
+		}
+	}
+}
@@ -71,6 +71,7 @@ func (s *Server) Run() {
 	v1.Use(s.verifyAuthMiddleware)
 	v1.GET("/clipboard", s.getClipboardHandler)
 	v1.POST("/clipboard", s.updateClipboardHandler)
+	v1.GET("/clipboard/events", s.clipboardEventsHandler)
 	s.log.Info("The server has started!")
 	if s.config.TLS {
 		if err := r.RunTLS(s.config.Listen, s.config.TLSCertFile, s.config.TLSKeyFile); err != nil {
@@ -113,6 +114,7 @@ func (s *Server) getClipboardHandler(c *gin.Context) {
 	c.Header("X-Type", cb.Type)
 	c.Header("X-FileName", cb.FileName)
 	c.Header("X-ClientName", cb.ClientName)
+	c.Header("X-CreatedAt", strconv.FormatInt(cb.CreatedAt.UnixMilli(), 10))
 	if _, err := c.Writer.Write(cb.Data); err != nil {
 		c.JSON(http.StatusInternalServerError, gin.H{"message": "Write data failed"})
 		s.log.Error(err)
@@ -164,9 +166,10 @@ func (s *Server) updateClipboardHandler(c *gin.Context) {
 		index = cb.Index
 	}
 
-	var clientName string
+	// Clients may name themselves; otherwise the name is derived from the User-Agent
+	clientName := c.Request.Header.Get("X-ClientName")
 	ua := useragent.Parse(c.Request.Header.Get("User-Agent"))
-	if ua.OS != "" {
+	if clientName == "" && ua.OS != "" {
 		clientName = ua.OS
 		if ua.Name != "" {
 			clientName += fmt.Sprintf(" %s", ua.Name)
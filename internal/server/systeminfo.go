@@ -5,10 +5,13 @@ import (
 	"time"
 
 	"github.com/gin-gonic/gin"
+	"github.com/llaoj/gcopy/pkg/version"
 )
 
 func (s *Server) getSystemInfoHandler(c *gin.Context) {
 	c.JSON(http.StatusOK, gin.H{
-		"time": time.Now(),
+		"time":             time.Now(),
+		"version":          version.Version(),
+		"maxContentLength": s.config.MaxContentLength * 1024 * 1024,
 	})
 }
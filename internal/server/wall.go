@@ -13,16 +13,53 @@ import (
 type Wall struct {
 	log *logrus.Logger
 	cbs sync.Map
+
+	mu   sync.Mutex
+	subs map[string]map[chan int]struct{}
 }
 
 func NewWall(log *logrus.Logger) *Wall {
 	return &Wall{
-		log: log,
+		log:  log,
+		subs: make(map[string]map[chan int]struct{}),
 	}
 }
 
 func (w *Wall) Set(key string, cb *gcopy.Clipboard) {
 	w.cbs.Store(key, cb)
+
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	for ch := range w.subs[key] {
+		// Only the newest index matters, replace one not yet received
+		select {
+		case <-ch:
+		default:
+		}
+		ch <- cb.Index
+	}
+}
+
+// Subscribe returns a channel receiving the index of every clipboard set
+// under the key, and a function ending the subscription.
+func (w *Wall) Subscribe(key string) (<-chan int, func()) {
+	ch := make(chan int, 1)
+
+	w.mu.Lock()
+	if w.subs[key] == nil {
+		w.subs[key] = make(map[chan int]struct{})
+	}
+	w.subs[key][ch] = struct{}{}
+	w.mu.Unlock()
+
+	return ch, func() {
+		w.mu.Lock()
+		defer w.mu.Unlock()
+		delete(w.subs[key], ch)
+		if len(w.subs[key]) == 0 {
+			delete(w.subs, key)
+		}
+	}
 }
 
 func (w *Wall) Get(key string) *gcopy.Clipboard {